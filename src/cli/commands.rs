@@ -0,0 +1,667 @@
+//! CLI command registry
+//!
+//! This module defines the commands available in the Pico shell. Each entry in
+//! [`cli_commands`] pairs a command name and help text with the handler that runs
+//! when the user submits a line starting with that name. The argument candidates
+//! listed alongside each command drive tab completion.
+use crate::cli::{dashboard, setup, status_bar};
+use crate::constants::{
+    MAX_COMPLETION_CANDIDATES, MAX_LINE_LENGTH, MAX_WORD_SEPARATORS, ONBOARD_LED_NUM,
+    PROMPT_CONTEXT_LENGTH, PROMPT_TEMPLATE_LENGTH,
+};
+use crate::peripherals::gpio::{NUM_PINS, with_gpio};
+use crate::peripherals::uart::ByteIo;
+use crate::peripherals::uart::print::cli_println;
+use crate::peripherals::uart::status_bar::StatusPosition;
+use crate::peripherals::uart::terminal::{
+    DEFAULT_WORD_SEPARATORS, EXIT_FAILURE, EXIT_USAGE, EditMode, InputLineEnding, OutputNewline,
+    Terminal,
+};
+
+/// Signature of a command handler
+///
+/// The handler receives the terminal to print to and the arguments that followed
+/// the command name on the submitted line. A handler that fails reports it with
+/// [`Terminal::set_exit_status`].
+pub type CommandHandler<IO> = fn(terminal: &mut Terminal<IO>, args: &[&str]);
+
+/// A single entry in the command registry
+pub struct Command<IO: ByteIo> {
+    /// Name typed by the user to invoke the command
+    pub name: &'static str,
+
+    /// One-line description shown by `help`
+    pub help: &'static str,
+
+    /// Candidate values for each positional argument, used for tab completion
+    pub args: &'static [&'static [&'static str]],
+
+    /// Candidates for the arguments after a first argument that selects what follows,
+    /// used for tab completion instead of `args` from the second argument on
+    pub subcommands: &'static [Subcommand],
+
+    /// Function executed when the command is invoked
+    pub handler: CommandHandler<IO>,
+}
+
+/// Arguments following a first argument that selects what they mean, e.g. `newline out`
+pub struct Subcommand {
+    /// First argument selecting the subcommand
+    pub name: &'static str,
+
+    /// Candidate values for each argument after the name, used for tab completion
+    pub args: &'static [&'static [&'static str]],
+}
+
+/// Names accepted wherever a GPIO pin is expected
+const PIN_NAMES: &[&str] = &[
+    "GP0", "GP1", "GP2", "GP3", "GP4", "GP5", "GP6", "GP7", "GP8", "GP9", "GP10", "GP11", "GP12",
+    "GP13", "GP14", "GP15", "GP16", "GP17", "GP18", "GP19", "GP20", "GP21", "GP22", "GP23", "GP24",
+    "GP25", "GP26", "GP27", "GP28", "GP29", "LED",
+];
+
+/// Actions accepted by the `gpio` command
+const GPIO_ACTIONS: &[&str] = &["high", "low", "input", "output", "read"];
+
+/// Input line endings accepted by `newline in`
+const INPUT_LINE_ENDINGS: &[&str] = &["auto", "cr", "lf", "crlf"];
+
+/// Output newlines accepted by `newline out`
+const OUTPUT_NEWLINES: &[&str] = &["crlf", "lf", "cr"];
+
+/// Directions configured by the `newline` command, each with its own line endings
+const NEWLINE_DIRECTIONS: &[Subcommand] = &[
+    Subcommand {
+        name: "in",
+        args: &[INPUT_LINE_ENDINGS],
+    },
+    Subcommand {
+        name: "out",
+        args: &[OUTPUT_NEWLINES],
+    },
+];
+
+/// Returns all commands understood by the shell
+pub fn cli_commands<IO: ByteIo>() -> &'static [Command<IO>] {
+    &[
+        Command {
+            name: "help",
+            help: "List available commands",
+            args: &[],
+            subcommands: &[],
+            handler: help::<IO>,
+        },
+        Command {
+            name: "clear",
+            help: "Clear the screen",
+            args: &[],
+            subcommands: &[],
+            handler: clear::<IO>,
+        },
+        Command {
+            name: "echo",
+            help: "Print the given arguments",
+            args: &[],
+            subcommands: &[],
+            handler: echo::<IO>,
+        },
+        Command {
+            name: "led",
+            help: "Switch the onboard LED: led <on|off>",
+            args: &[&["on", "off"]],
+            subcommands: &[],
+            handler: led::<IO>,
+        },
+        Command {
+            name: "gpio",
+            help: "Drive or read a pin: gpio <pin> <high|low|input|output|read>",
+            args: &[PIN_NAMES, GPIO_ACTIONS],
+            subcommands: &[],
+            handler: gpio::<IO>,
+        },
+        Command {
+            name: "color",
+            help: "Switch coloured output: color <on|off>",
+            args: &[&["on", "off"]],
+            subcommands: &[],
+            handler: color::<IO>,
+        },
+        Command {
+            name: "plain",
+            help: "Send no escape sequences, for dumb terminals and logs: plain [on|off]",
+            args: &[&["on", "off"]],
+            subcommands: &[],
+            handler: plain::<IO>,
+        },
+        Command {
+            name: "newline",
+            help: "Set line endings: newline in <auto|cr|lf|crlf> | newline out <crlf|lf|cr>",
+            args: &[&["in", "out"]],
+            subcommands: NEWLINE_DIRECTIONS,
+            handler: newline::<IO>,
+        },
+        Command {
+            name: "width",
+            help: "Show or set the terminal width: width [auto|<columns>]",
+            args: &[&["auto"]],
+            subcommands: &[],
+            handler: width::<IO>,
+        },
+        Command {
+            name: "height",
+            help: "Show or set the terminal height for paging: height [auto|off|<rows>]",
+            args: &[&["auto", "off"]],
+            subcommands: &[],
+            handler: height::<IO>,
+        },
+        Command {
+            name: "prompt",
+            help: "Set the prompt: prompt [reset|<template>] with {uptime} {status} {uart} {context}",
+            args: &[&["reset"]],
+            subcommands: &[],
+            handler: prompt::<IO>,
+        },
+        Command {
+            name: "context",
+            help: "Set the text shown by {context} in the prompt: context [<text>]",
+            args: &[],
+            subcommands: &[],
+            handler: context::<IO>,
+        },
+        Command {
+            name: "editmode",
+            help: "Show or set the line editing keys: editmode [emacs|vi]",
+            args: &[&["emacs", "vi"]],
+            subcommands: &[],
+            handler: editmode::<IO>,
+        },
+        Command {
+            name: "wordsep",
+            help: "Show or set the characters ending a word: wordsep [reset|<characters>]",
+            args: &[&["reset"]],
+            subcommands: &[],
+            handler: wordsep::<IO>,
+        },
+        Command {
+            name: "top",
+            help: "Show a live view of pins, clocks, UART errors and uptime",
+            args: &[],
+            subcommands: &[],
+            handler: top::<IO>,
+        },
+        Command {
+            name: "statusbar",
+            help: "Show or pin a status bar: statusbar [top|bottom|off]",
+            args: &[&["top", "bottom", "off"]],
+            subcommands: &[],
+            handler: statusbar::<IO>,
+        },
+        Command {
+            name: "setup",
+            help: "Configure the terminal, pins and prompt from menus",
+            args: &[],
+            subcommands: &[],
+            handler: setup_command::<IO>,
+        },
+    ]
+}
+
+/// Looks up a command by name
+///
+/// # Arguments
+///
+/// * `commands` - Registry to search
+/// * `name` - Command name typed by the user
+///
+/// # Returns
+///
+/// The matching command, or `None` if the name is unknown
+pub fn find_command<'a, IO: ByteIo>(
+    commands: &'a [Command<IO>],
+    name: &str,
+) -> Option<&'a Command<IO>> {
+    commands.iter().find(|command| command.name == name)
+}
+
+/// Collects the completion candidates for a word being typed
+///
+/// The first word completes to command names; later words complete to the
+/// candidates the command declares for that argument position, or from the second
+/// argument on to those of the subcommand named by the first argument.
+///
+/// # Arguments
+///
+/// * `commands` - Registry providing the candidates
+/// * `preceding` - Complete words before the one being typed
+/// * `prefix` - Partial word being typed
+///
+/// # Returns
+///
+/// The candidates starting with `prefix`, in declaration order
+pub fn completion_candidates<IO: ByteIo>(
+    commands: &[Command<IO>],
+    preceding: &[&str],
+    prefix: &str,
+) -> heapless::Vec<&'static str, MAX_COMPLETION_CANDIDATES> {
+    let mut candidates = heapless::Vec::new();
+    let mut add = |candidate: &'static str| {
+        if candidate.starts_with(prefix) {
+            let _ = candidates.push(candidate);
+        }
+    };
+
+    match preceding.split_first() {
+        None => commands.iter().for_each(|command| add(command.name)),
+        Some((name, args)) => {
+            let values = find_command(commands, name).and_then(|command| {
+                let subcommand = args.first().and_then(|first| {
+                    command
+                        .subcommands
+                        .iter()
+                        .find(|subcommand| subcommand.name == *first)
+                });
+                match subcommand {
+                    Some(subcommand) => subcommand.args.get(args.len() - 1),
+                    None => command.args.get(args.len()),
+                }
+            });
+            if let Some(values) = values {
+                values.iter().for_each(|&value| add(value));
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Parses a pin name such as `GP25`, `25` or `LED` into a pin number
+fn parse_pin(name: &str) -> Option<usize> {
+    if name.eq_ignore_ascii_case("LED") {
+        return Some(ONBOARD_LED_NUM);
+    }
+
+    let digits = name
+        .strip_prefix("GP")
+        .or_else(|| name.strip_prefix("gp"))
+        .unwrap_or(name);
+    digits.parse().ok().filter(|&pin| pin < NUM_PINS)
+}
+
+/// Prints a usage hint and marks the command as failed
+///
+/// # Arguments
+///
+/// * `terminal` - Terminal to print to
+/// * `usage` - Accepted syntax of the command
+fn usage<IO: ByteIo>(terminal: &mut Terminal<IO>, usage: &str) {
+    cli_println!(terminal, warning, "usage: {}", usage);
+    terminal.set_exit_status(EXIT_USAGE);
+}
+
+/// Joins arguments with single spaces
+///
+/// # Returns
+///
+/// The joined text, or `None` if it is longer than `N` bytes
+fn join_args<const N: usize>(args: &[&str]) -> Option<heapless::String<N>> {
+    let mut text = heapless::String::new();
+    for (index, arg) in args.iter().enumerate() {
+        if index > 0 {
+            text.push(' ').ok()?;
+        }
+        text.push_str(arg).ok()?;
+    }
+    Some(text)
+}
+
+/// Prints every registered command together with its help text
+///
+/// Commands are listed one at a time, so the list is paged without being held in full.
+fn help<IO: ByteIo>(terminal: &mut Terminal<IO>, _args: &[&str]) {
+    let count = terminal.commands().len();
+    terminal.produce_output(count, help_entry::<IO>);
+}
+
+/// Prints the help line of one command, with the names of all commands aligned
+///
+/// # Arguments
+///
+/// * `terminal` - Terminal to print to
+/// * `index` - Position of the command in the registry
+fn help_entry<IO: ByteIo>(terminal: &mut Terminal<IO>, index: usize) {
+    let commands = terminal.commands();
+    let name_width = commands
+        .iter()
+        .map(|command| command.name.len())
+        .max()
+        .unwrap_or(0);
+
+    cli_println!(
+        terminal,
+        "  {:<width$}{}",
+        commands[index].name,
+        commands[index].help,
+        width = name_width + 2
+    );
+}
+
+/// Clears the screen
+fn clear<IO: ByteIo>(terminal: &mut Terminal<IO>, _args: &[&str]) {
+    terminal.clear_screen();
+}
+
+/// Prints the arguments separated by single spaces
+fn echo<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    for (index, arg) in args.iter().enumerate() {
+        if index > 0 {
+            terminal.print(b" ", false);
+        }
+        terminal.print(arg.as_bytes(), false);
+    }
+    terminal.print_line(b"");
+}
+
+/// Switches the onboard LED on or off
+fn led<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    let high = match args {
+        ["on"] => true,
+        ["off"] => false,
+        _ => {
+            usage(terminal, "led <on|off>");
+            return;
+        }
+    };
+
+    let installed = with_gpio(|gpio| {
+        if high {
+            gpio.set_high(ONBOARD_LED_NUM);
+        } else {
+            gpio.set_low(ONBOARD_LED_NUM);
+        }
+    });
+
+    if installed.is_none() {
+        cli_println!(terminal, error, "led: GPIO not initialised");
+        terminal.set_exit_status(EXIT_FAILURE);
+    }
+}
+
+/// Drives, configures or reads a single GPIO pin
+fn gpio<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    const USAGE: &str = "gpio <GP0..GP29|LED> <high|low|input|output|read>";
+
+    let (Some(pin), Some(&action)) = (args.first().and_then(|name| parse_pin(name)), args.get(1))
+    else {
+        usage(terminal, USAGE);
+        return;
+    };
+
+    if !GPIO_ACTIONS.contains(&action) {
+        usage(terminal, USAGE);
+        return;
+    }
+
+    // `Some(level)` is returned only for reads
+    let result = with_gpio(|gpio| match action {
+        "high" => {
+            gpio.set_high(pin);
+            None
+        }
+        "low" => {
+            gpio.set_low(pin);
+            None
+        }
+        "input" => {
+            gpio.set_input(pin);
+            None
+        }
+        "output" => {
+            gpio.set_output(pin);
+            None
+        }
+        _ => Some(gpio.read(pin)),
+    });
+
+    match result {
+        None => {
+            cli_println!(terminal, error, "gpio: GPIO not initialised");
+            terminal.set_exit_status(EXIT_FAILURE);
+        }
+        Some(Some(true)) => terminal.print_line(b"high"),
+        Some(Some(false)) => terminal.print_line(b"low"),
+        Some(None) => {}
+    }
+}
+
+/// Selects the line ending expected on input or written on output
+fn newline<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    match args {
+        ["in", mode] => {
+            let line_ending = match *mode {
+                "auto" => InputLineEnding::Auto,
+                "cr" => InputLineEnding::Cr,
+                "lf" => InputLineEnding::Lf,
+                "crlf" => InputLineEnding::CrLf,
+                _ => {
+                    usage(terminal, "newline in <auto|cr|lf|crlf>");
+                    return;
+                }
+            };
+            terminal.set_input_line_ending(line_ending);
+        }
+        ["out", mode] => {
+            let newline = match *mode {
+                "crlf" => OutputNewline::CrLf,
+                "lf" => OutputNewline::Lf,
+                "cr" => OutputNewline::Cr,
+                _ => {
+                    usage(terminal, "newline out <crlf|lf|cr>");
+                    return;
+                }
+            };
+            terminal.set_output_newline(newline);
+        }
+        _ => usage(
+            terminal,
+            "newline in <auto|cr|lf|crlf> | newline out <crlf|lf|cr>",
+        ),
+    }
+}
+
+/// Shows the terminal width, sets it, or queries it from the terminal
+fn width<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    match args {
+        [] => {
+            let columns = terminal.width();
+            cli_println!(terminal, "{} columns", columns);
+        }
+        ["auto"] => terminal.request_size(),
+        [columns] => match columns.parse() {
+            Ok(columns) if columns > 0 => terminal.set_width(columns),
+            _ => usage(terminal, "width [auto|<columns>]"),
+        },
+        _ => usage(terminal, "width [auto|<columns>]"),
+    }
+}
+
+/// Shows the terminal height used for paging, sets it, queries it from the terminal or
+/// turns paging off
+fn height<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    match args {
+        [] => match terminal.height() {
+            Some(rows) => cli_println!(terminal, "{} rows", rows),
+            None => cli_println!(terminal, "unknown, output is not paged"),
+        },
+        ["auto"] => terminal.request_size(),
+        ["off"] => terminal.set_height(None),
+        [rows] => match rows.parse() {
+            Ok(rows) if rows > 1 => terminal.set_height(Some(rows)),
+            _ => usage(terminal, "height [auto|off|<rows>]"),
+        },
+        _ => usage(terminal, "height [auto|off|<rows>]"),
+    }
+}
+
+/// Enables or disables colours and text attributes in all output
+fn color<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    match args {
+        ["on"] => terminal.set_styling(true),
+        ["off"] => terminal.set_styling(false),
+        _ => usage(terminal, "color <on|off>"),
+    }
+}
+
+/// Shows or switches plain output, which sends no escape sequences at all
+fn plain<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    match args {
+        [] => match terminal.plain() {
+            true => terminal.print_line(b"on"),
+            false => terminal.print_line(b"off"),
+        },
+        ["on"] => terminal.set_plain(true),
+        ["off"] => terminal.set_plain(false),
+        _ => usage(terminal, "plain [on|off]"),
+    }
+}
+
+/// Shows, sets or resets the prompt template
+///
+/// The arguments are joined with single spaces and followed by a space, which
+/// separates the prompt from the typed text.
+fn prompt<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    match args {
+        [] => {
+            let mut template: heapless::String<PROMPT_TEMPLATE_LENGTH> = heapless::String::new();
+            let _ = template.push_str(terminal.prompt_template());
+            cli_println!(terminal, "{:?}", template.as_str());
+        }
+        ["reset"] => terminal.reset_prompt_template(),
+        _ => {
+            let template = join_args::<MAX_LINE_LENGTH>(args).and_then(|mut template| {
+                template.push(' ').ok()?;
+                Some(template)
+            });
+            if !template.is_some_and(|template| terminal.set_prompt_template(&template)) {
+                cli_println!(
+                    terminal,
+                    error,
+                    "prompt: template longer than {} bytes",
+                    PROMPT_TEMPLATE_LENGTH
+                );
+                terminal.set_exit_status(EXIT_FAILURE);
+            }
+        }
+    }
+}
+
+/// Sets or clears the text shown by the `{context}` prompt placeholder
+fn context<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    let set =
+        join_args::<PROMPT_CONTEXT_LENGTH>(args).is_some_and(|text| terminal.set_context(&text));
+    if !set {
+        cli_println!(
+            terminal,
+            error,
+            "context: text longer than {} bytes",
+            PROMPT_CONTEXT_LENGTH
+        );
+        terminal.set_exit_status(EXIT_FAILURE);
+    }
+}
+
+/// Shows or selects emacs-style or vi-style line editing
+fn editmode<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    match args {
+        [] => match terminal.edit_mode() {
+            EditMode::Emacs => terminal.print_line(b"emacs"),
+            EditMode::Vi => terminal.print_line(b"vi"),
+        },
+        ["emacs"] => terminal.set_edit_mode(EditMode::Emacs),
+        ["vi"] => terminal.set_edit_mode(EditMode::Vi),
+        _ => usage(terminal, "editmode [emacs|vi]"),
+    }
+}
+
+/// Shows, sets or resets the characters that separate words for word motion and deletion
+///
+/// Space and tab always separate words, as they cannot be typed in an argument.
+fn wordsep<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    match args {
+        [] => {
+            let mut separators: heapless::String<MAX_WORD_SEPARATORS> = heapless::String::new();
+            for &byte in terminal.word_separators() {
+                let _ = separators.push(char::from(byte));
+            }
+            cli_println!(terminal, "{:?}", separators.as_str());
+        }
+        ["reset"] => {
+            terminal.set_word_separators(DEFAULT_WORD_SEPARATORS);
+        }
+        [characters] => {
+            let mut separators: heapless::Vec<u8, MAX_LINE_LENGTH> = heapless::Vec::new();
+            let _ = separators.extend_from_slice(b" \t");
+            let _ = separators.extend_from_slice(characters.as_bytes());
+            if !terminal.set_word_separators(&separators) {
+                cli_println!(
+                    terminal,
+                    error,
+                    "wordsep: at most {} ASCII characters, including space and tab",
+                    MAX_WORD_SEPARATORS
+                );
+                terminal.set_exit_status(EXIT_FAILURE);
+            }
+        }
+        _ => usage(terminal, "wordsep [reset|<characters>]"),
+    }
+}
+
+/// Shows the live dashboard until a key is pressed
+fn top<IO: ByteIo>(terminal: &mut Terminal<IO>, _args: &[&str]) {
+    terminal.show_view(dashboard::draw::<IO>);
+}
+
+/// Shows, pins or removes the status bar
+fn statusbar<IO: ByteIo>(terminal: &mut Terminal<IO>, args: &[&str]) {
+    match args {
+        [] => match terminal.status_bar_position() {
+            Some(StatusPosition::Top) => terminal.print_line(b"top"),
+            Some(StatusPosition::Bottom) => terminal.print_line(b"bottom"),
+            None => terminal.print_line(b"off"),
+        },
+        ["top"] => terminal.show_status_bar(StatusPosition::Top, status_bar::draw::<IO>),
+        ["bottom"] => terminal.show_status_bar(StatusPosition::Bottom, status_bar::draw::<IO>),
+        ["off"] => terminal.hide_status_bar(),
+        _ => usage(terminal, "statusbar [top|bottom|off]"),
+    }
+}
+
+/// Shows the setup menus
+fn setup_command<IO: ByteIo>(terminal: &mut Terminal<IO>, _args: &[&str]) {
+    setup::show(terminal);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::uart::fake::FakeIo;
+
+    #[test]
+    fn newline_completes_the_line_endings_of_each_direction() {
+        // The full registry would pull the GPIO handlers into the host build
+        let commands = &[Command::<FakeIo> {
+            name: "newline",
+            help: "",
+            args: &[&["in", "out"]],
+            subcommands: NEWLINE_DIRECTIONS,
+            handler: newline::<FakeIo>,
+        }];
+
+        let directions = completion_candidates(commands, &["newline"], "");
+        assert_eq!(directions.as_slice(), ["in", "out"]);
+        let input = completion_candidates(commands, &["newline", "in"], "");
+        assert_eq!(input.as_slice(), ["auto", "cr", "lf", "crlf"]);
+        let output = completion_candidates(commands, &["newline", "out"], "");
+        assert_eq!(output.as_slice(), ["crlf", "lf", "cr"]);
+        assert!(completion_candidates(commands, &["newline", "out", "cr"], "").is_empty());
+    }
+}
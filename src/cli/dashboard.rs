@@ -0,0 +1,278 @@
+//! Live dashboard shown by the `top` command
+//!
+//! The dashboard is a full-screen view of the board state, redrawn by the terminal
+//! until a key is pressed, see [`Terminal::show_view`]. Each part of the state has a
+//! fixed region of the screen:
+//! - Uptime in the header
+//! - Clock frequencies
+//! - UART receive error counters
+//! - Function, direction and level of the 30 GPIO pins, in three columns
+use crate::clocks::{ClockFrequencies, frequencies};
+use crate::constants::VIEW_COLUMNS;
+use crate::peripherals::gpio::{NUM_PINS, with_gpio};
+use crate::peripherals::timer::uptime_us;
+use crate::peripherals::uart::prompt::write_uptime;
+use crate::peripherals::uart::screen::Frame;
+use crate::peripherals::uart::terminal::Terminal;
+use crate::peripherals::uart::{ByteIo, UartErrors};
+use core::fmt::Write;
+
+/// Row of the header with the uptime
+const HEADER_ROW: usize = 0;
+
+/// Row of the clocks section title, followed by two rows of frequencies
+const CLOCKS_ROW: usize = 2;
+
+/// Row of the UART section title, followed by a row of counters
+const UART_ROW: usize = 6;
+
+/// Row of the GPIO section title, followed by the column headings and the pins
+const GPIO_ROW: usize = 9;
+
+/// Number of pins listed in each column of the GPIO section
+const PINS_PER_COLUMN: usize = 10;
+
+/// Width in cells of each column of the GPIO section
+const PIN_COLUMN_WIDTH: usize = 26;
+
+/// Hint shown at the right of the header
+const EXIT_HINT: &str = "press any key to return";
+
+/// State of one GPIO pin
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PinState {
+    /// Function selected for the pin, as the `FUNCSEL` value
+    pub function: u8,
+
+    /// Whether the pin is configured as an output
+    pub output: bool,
+
+    /// Whether the pin reads high
+    pub high: bool,
+}
+
+/// Board state shown by the dashboard; parts that cannot be read are `None`
+pub struct Snapshot {
+    /// Time since start-up in microseconds
+    pub uptime_us: Option<u64>,
+
+    /// Clock frequencies
+    pub clocks: Option<ClockFrequencies>,
+
+    /// Receive error counters of the UART the shell runs on
+    pub uart_errors: Option<UartErrors>,
+
+    /// State of every GPIO pin
+    pub pins: Option<[PinState; NUM_PINS]>,
+}
+
+impl Snapshot {
+    /// Reads the current state of the board
+    ///
+    /// # Arguments
+    ///
+    /// * `terminal` - Terminal whose transport provides the UART error counters
+    pub fn capture<IO: ByteIo>(terminal: &Terminal<IO>) -> Self {
+        Snapshot {
+            uptime_us: uptime_us(),
+            clocks: frequencies(),
+            uart_errors: terminal.uart_errors(),
+            pins: with_gpio(|gpio| {
+                core::array::from_fn(|pin| PinState {
+                    function: gpio.function(pin),
+                    output: gpio.is_output(pin),
+                    high: gpio.read(pin),
+                })
+            }),
+        }
+    }
+}
+
+/// Draws the dashboard with the current board state, see [`Terminal::show_view`]
+pub fn draw<IO: ByteIo>(terminal: &Terminal<IO>, frame: &mut Frame) {
+    render(&Snapshot::capture(terminal), frame);
+}
+
+/// Draws the dashboard for a snapshot of the board state
+///
+/// # Arguments
+///
+/// * `snapshot` - State to show
+/// * `frame` - Blank frame to draw into
+fn render(snapshot: &Snapshot, frame: &mut Frame) {
+    let mut header = frame.at(HEADER_ROW, 0);
+    let _ = header.write_str("PICO TOP   uptime ");
+    match snapshot.uptime_us {
+        Some(uptime_us) => write_uptime(&mut header, uptime_us),
+        None => {
+            let _ = header.write_str("-");
+        }
+    }
+    let _ = frame
+        .at(HEADER_ROW, VIEW_COLUMNS - EXIT_HINT.len())
+        .write_str(EXIT_HINT);
+
+    let _ = frame.at(CLOCKS_ROW, 0).write_str("Clocks");
+    match &snapshot.clocks {
+        Some(clocks) => {
+            let rows = [
+                [
+                    ("sys", clocks.system),
+                    ("peri", clocks.peripheral),
+                    ("ref", clocks.reference),
+                ],
+                [
+                    ("usb", clocks.usb),
+                    ("adc", clocks.adc),
+                    ("rtc", clocks.rtc),
+                ],
+            ];
+            for (row, clocks) in rows.iter().enumerate() {
+                let mut line = frame.at(CLOCKS_ROW + 1 + row, 0);
+                for (name, hz) in clocks {
+                    let _ = write!(
+                        line,
+                        "  {:<4} {:>3}.{:03} MHz  ",
+                        name,
+                        hz / 1_000_000,
+                        hz / 1000 % 1000
+                    );
+                }
+            }
+        }
+        None => {
+            let _ = frame.at(CLOCKS_ROW + 1, 0).write_str("  unavailable");
+        }
+    }
+
+    let _ = frame.at(UART_ROW, 0).write_str("UART errors");
+    let mut line = frame.at(UART_ROW + 1, 0);
+    let _ = match &snapshot.uart_errors {
+        Some(errors) => write!(
+            line,
+            "  overrun {}   break {}   parity {}   framing {}   queue full {}",
+            errors.overrun, errors.breaks, errors.parity, errors.framing, errors.queue_full
+        ),
+        None => line.write_str("  not counted on this transport"),
+    };
+
+    let _ = frame.at(GPIO_ROW, 0).write_str("GPIO");
+    let Some(pins) = &snapshot.pins else {
+        let _ = frame.at(GPIO_ROW + 1, 0).write_str("  not initialised");
+        return;
+    };
+    for column in 0..NUM_PINS.div_ceil(PINS_PER_COLUMN) {
+        let _ = frame
+            .at(GPIO_ROW + 1, column * PIN_COLUMN_WIDTH)
+            .write_str("  pin  func  dir  level");
+    }
+    for (pin, state) in pins.iter().enumerate() {
+        let _ = write!(
+            frame.at(
+                GPIO_ROW + 2 + pin % PINS_PER_COLUMN,
+                pin / PINS_PER_COLUMN * PIN_COLUMN_WIDTH
+            ),
+            "  GP{:<2} {:<5} {:<4} {}",
+            pin,
+            function_name(state.function),
+            if state.output { "out" } else { "in" },
+            if state.high { "high" } else { "low" }
+        );
+    }
+}
+
+/// Returns the name of a GPIO function select value
+fn function_name(function: u8) -> &'static str {
+    match function {
+        1 => "SPI",
+        2 => "UART",
+        3 => "I2C",
+        4 => "PWM",
+        5 => "SIO",
+        6 => "PIO0",
+        7 => "PIO1",
+        8 => "CLOCK",
+        9 => "USB",
+        0x1F => "NULL",
+        _ => "?",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row_text(frame: &Frame, row: usize) -> &str {
+        core::str::from_utf8(frame.row(row)).unwrap().trim_end()
+    }
+
+    #[test]
+    fn renders_every_region() {
+        let mut pins = [PinState {
+            function: 0x1F,
+            ..PinState::default()
+        }; NUM_PINS];
+        pins[0].function = 2;
+        pins[25] = PinState {
+            function: 5,
+            output: true,
+            high: true,
+        };
+        let snapshot = Snapshot {
+            uptime_us: Some(3_723_000_000),
+            clocks: Some(ClockFrequencies {
+                system: 125_000_000,
+                reference: 12_000_000,
+                peripheral: 125_000_000,
+                usb: 48_000_000,
+                adc: 48_000_000,
+                rtc: 46_875,
+            }),
+            uart_errors: Some(UartErrors {
+                framing: 2,
+                ..UartErrors::default()
+            }),
+            pins: Some(pins),
+        };
+
+        let mut frame = Frame::new();
+        render(&snapshot, &mut frame);
+
+        assert!(row_text(&frame, HEADER_ROW).starts_with("PICO TOP   uptime 01:02:03"));
+        assert!(row_text(&frame, HEADER_ROW).ends_with(EXIT_HINT));
+        assert_eq!(
+            row_text(&frame, CLOCKS_ROW + 2),
+            "  usb   48.000 MHz    adc   48.000 MHz    rtc    0.046 MHz"
+        );
+        assert_eq!(
+            row_text(&frame, UART_ROW + 1),
+            "  overrun 0   break 0   parity 0   framing 2   queue full 0"
+        );
+        assert_eq!(
+            row_text(&frame, GPIO_ROW + 2),
+            "  GP0  UART  in   low       GP10 NULL  in   low       GP20 NULL  in   low"
+        );
+        assert!(row_text(&frame, GPIO_ROW + 7).ends_with("GP25 SIO   out  high"));
+    }
+
+    #[test]
+    fn missing_state_is_marked() {
+        let snapshot = Snapshot {
+            uptime_us: None,
+            clocks: None,
+            uart_errors: None,
+            pins: None,
+        };
+
+        let mut frame = Frame::new();
+        render(&snapshot, &mut frame);
+
+        assert!(row_text(&frame, HEADER_ROW).starts_with("PICO TOP   uptime -"));
+        assert_eq!(row_text(&frame, CLOCKS_ROW + 1), "  unavailable");
+        assert_eq!(
+            row_text(&frame, UART_ROW + 1),
+            "  not counted on this transport"
+        );
+        assert_eq!(row_text(&frame, GPIO_ROW + 1), "  not initialised");
+    }
+}
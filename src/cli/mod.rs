@@ -3,7 +3,16 @@
 //! This module provides a high-level interface for a UART-based command line interface,
 //! featuring a customized shell prompt and banner. It wraps the lower-level terminal
 //! functionality into a user-friendly CLI interface.
-use crate::peripherals::uart::terminal::{Terminal, TerminalTextColor};
+pub mod commands;
+pub mod dashboard;
+pub mod setup;
+pub mod status_bar;
+
+use crate::cli::commands::cli_commands;
+use crate::peripherals::uart::style::{Color, Style};
+use crate::peripherals::uart::terminal::Terminal;
+use crate::peripherals::uart::{ByteIo, Uart};
+use core::fmt;
 
 use rp2040_pac::{RESETS, UART0};
 
@@ -25,12 +34,13 @@ const CLI_PROMPT: &[u8] = b"[PICO]$ ";
 ///
 /// Provides a high-level interface for interacting with the UART console,
 /// managing the terminal and command processing.
-pub struct Cli {
+pub struct Cli<IO: ByteIo> {
     /// The underlying terminal instance
-    line_editor: Terminal,
+    line_editor: Terminal<IO>,
 }
 
-impl Cli {
+#[cfg_attr(feature = "sim", allow(dead_code))]
+impl Cli<Uart> {
     /// Creates a new CLI instance
     ///
     /// # Arguments
@@ -41,7 +51,7 @@ impl Cli {
     ///
     /// # Returns
     ///
-    /// A new `Cli` instance configured with the default banner and blue prompt
+    /// A new `Cli` instance configured with the default banner, blue prompt and command set
     ///
     /// # Example
     ///
@@ -49,13 +59,28 @@ impl Cli {
     /// let mut cli = Cli::new(uart0, &mut resets, clocks.uart_clock_freq());
     /// ```
     pub fn new(uart_peripheral: UART0, resets: &mut RESETS, uart_clock_freq: u32) -> Self {
+        let uart = Uart::new(uart_peripheral, uart_clock_freq, resets);
+        Cli::with_io(uart)
+    }
+}
+
+impl<IO: ByteIo> Cli<IO> {
+    /// Creates a new CLI instance on top of an arbitrary transport
+    ///
+    /// # Arguments
+    ///
+    /// * `io` - Transport to read input from and write output to
+    ///
+    /// # Returns
+    ///
+    /// A new `Cli` instance configured with the default banner, blue prompt and command set
+    pub fn with_io(io: IO) -> Self {
         let line_editor = Terminal::new(
-            uart_peripheral,
-            uart_clock_freq,
-            resets,
-            TerminalTextColor::Blue,
+            io,
+            Style::new().foreground(Color::Blue),
             CLI_BANNER,
             CLI_PROMPT,
+            cli_commands(),
         );
         Cli { line_editor }
     }
@@ -70,6 +95,32 @@ impl Cli {
         self.line_editor.print(s, false);
     }
 
+    /// Prints a message above the prompt without disturbing the line being edited
+    ///
+    /// Code that does not own the CLI, such as interrupt handlers, queues messages with
+    /// [`crate::peripherals::uart::log`] instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - Byte slice containing the message to print
+    #[allow(unused)]
+    pub fn print_above(&mut self, message: &[u8]) {
+        self.line_editor.print_above(message);
+    }
+
+    /// Writes formatted text in the given style
+    ///
+    /// Used by the [`cli_print!`](crate::peripherals::uart::print::cli_print) macros.
+    ///
+    /// # Arguments
+    ///
+    /// * `style` - Colours and attributes to apply
+    /// * `args` - Formatted text, as produced by `format_args!`
+    #[allow(unused)]
+    pub fn write_styled(&mut self, style: Style, args: fmt::Arguments) {
+        self.line_editor.write_styled(style, args);
+    }
+
     /// Processes any pending input from the UART
     ///
     /// This method should be called regularly (e.g., in the main loop) to handle
@@ -78,3 +129,9 @@ impl Cli {
         self.line_editor.process_input();
     }
 }
+
+impl<IO: ByteIo> fmt::Write for Cli<IO> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.line_editor.write_str(s)
+    }
+}
@@ -0,0 +1,572 @@
+//! Setup screens shown by the `setup` command
+//!
+//! The setup screens configure the shell with menus instead of typed commands, see
+//! [`Terminal::show_form`]. A main menu leads to one form per section:
+//! - UART: baud rate, data bits and parity of the UART the shell runs on
+//! - Terminal: line endings, colours, and the window size used for wrapping and paging
+//! - Pins: direction and level of a GPIO pin, chosen first and then shown in its current
+//!   mode
+//! - Prompt: prompt template and line editing keys
+//!
+//! Submitting a form applies its settings and cancelling it discards them; both return to
+//! the main menu. Choosing "Done" or pressing Esc in the main menu returns to the prompt.
+use crate::constants::{ONBOARD_LED_NUM, VIEW_ROWS};
+use crate::peripherals::gpio::{NUM_PINS, with_gpio};
+use crate::peripherals::uart::print::cli_println;
+use crate::peripherals::uart::terminal::{
+    EXIT_FAILURE, EditMode, InputLineEnding, OutputNewline, Terminal,
+};
+use crate::peripherals::uart::widgets::{Checkbox, Form, Menu, NumberField, Widget};
+use crate::peripherals::uart::{ByteIo, UartParity, UartSettings, UartWordLength};
+use core::ops::RangeInclusive;
+
+/// Sections listed in the main menu
+const SECTIONS: &[&str] = &["UART", "Terminal", "Pins", "Prompt", "Done"];
+
+/// Position of the UART section in [`SECTIONS`]
+const SECTION_UART: usize = 0;
+
+/// Position of the terminal section in [`SECTIONS`]
+const SECTION_TERMINAL: usize = 1;
+
+/// Position of the pins section in [`SECTIONS`]
+const SECTION_PINS: usize = 2;
+
+/// Position of the prompt section in [`SECTIONS`]
+const SECTION_PROMPT: usize = 3;
+
+/// Baud rates offered by the UART form
+const BAUD_RATES: &[&str] = &[
+    "9600", "19200", "38400", "57600", "115200", "230400", "460800", "921600",
+];
+
+/// Data bits per frame, in the order of [`word_length_index`]
+const WORD_LENGTHS: &[&str] = &["5", "6", "7", "8"];
+
+/// Parity settings, in the order of [`parity_index`]
+const PARITIES: &[&str] = &["none", "even", "odd"];
+
+/// Input line endings, in the order of [`input_line_ending_index`]
+const INPUT_LINE_ENDINGS: &[&str] = &["auto", "cr", "lf", "crlf"];
+
+/// Output newlines, in the order of [`output_newline_index`]
+const OUTPUT_NEWLINES: &[&str] = &["crlf", "lf", "cr"];
+
+/// Terminal widths accepted by the terminal form
+const WIDTHS: RangeInclusive<u32> = 20..=400;
+
+/// Terminal heights accepted by the terminal form
+const HEIGHTS: RangeInclusive<u32> = 2..=200;
+
+/// Pin modes offered by the pin mode form, in the order of [`pin_mode_index`]
+const PIN_MODES: &[&str] = &["input", "output low", "output high"];
+
+/// Prompt templates offered by the prompt form, after the entries keeping the current
+/// template and restoring the default one
+const PROMPT_PRESETS: &[&str] = &[
+    "keep current",
+    "default",
+    "[{uptime}]$ ",
+    "[{status}]$ ",
+    "[{context}]$ ",
+];
+
+/// Line editing modes, in the order of [`EditMode`]
+const EDIT_MODES: &[&str] = &["emacs", "vi"];
+
+/// Shows the main setup menu
+pub fn show<IO: ByteIo>(terminal: &mut Terminal<IO>) {
+    show_main_menu(terminal, SECTION_UART);
+}
+
+/// Shows the main setup menu with a section selected
+///
+/// # Arguments
+///
+/// * `terminal` - Terminal to show the menu on
+/// * `section` - Position of the selected section in [`SECTIONS`]
+fn show_main_menu<IO: ByteIo>(terminal: &mut Terminal<IO>, section: usize) {
+    let form = Form::new("SETUP").field("Section", Widget::Menu(Menu::new(SECTIONS, section)));
+    terminal.show_form(form, finish_main_menu::<IO>);
+}
+
+/// Opens the form of the section chosen in the main menu
+fn finish_main_menu<IO: ByteIo>(terminal: &mut Terminal<IO>, form: Option<&Form>) {
+    match form.and_then(|form| form.selected(0)) {
+        Some(SECTION_UART) => match terminal.uart_settings() {
+            Some(settings) => terminal.show_form(uart_form(&settings), finish_uart::<IO>),
+            None => {
+                cli_println!(
+                    terminal,
+                    error,
+                    "setup: the terminal does not run on a UART"
+                );
+                terminal.set_exit_status(EXIT_FAILURE);
+            }
+        },
+        Some(SECTION_TERMINAL) => {
+            let form = TerminalSettings::read(terminal).form();
+            terminal.show_form(form, finish_terminal::<IO>);
+        }
+        Some(SECTION_PINS) => terminal.show_form(pins_form(), finish_pins::<IO>),
+        Some(SECTION_PROMPT) => {
+            let form = prompt_form(terminal.edit_mode());
+            terminal.show_form(form, finish_prompt::<IO>);
+        }
+        _ => {}
+    }
+}
+
+/// Builds the UART form showing the settings
+///
+/// A baud rate that is not offered selects the default one.
+fn uart_form(settings: &UartSettings) -> Form {
+    let baud_rate = BAUD_RATES
+        .iter()
+        .position(|rate| rate.parse() == Ok(settings.baud_rate))
+        .or_else(|| {
+            let default = UartSettings::new().baud_rate;
+            BAUD_RATES
+                .iter()
+                .position(|rate| rate.parse() == Ok(default))
+        })
+        .unwrap_or(0);
+
+    Form::new("SETUP - UART")
+        .field("Baud rate", Widget::Menu(Menu::new(BAUD_RATES, baud_rate)))
+        .field(
+            "Data bits",
+            Widget::Menu(Menu::new(
+                WORD_LENGTHS,
+                word_length_index(settings.word_length),
+            )),
+        )
+        .field(
+            "Parity",
+            Widget::Menu(Menu::new(PARITIES, parity_index(settings.parity))),
+        )
+}
+
+/// Reads the settings from a submitted UART form
+///
+/// # Returns
+///
+/// The settings, or `None` if the form is not a UART form
+fn uart_settings_from_form(form: &Form) -> Option<UartSettings> {
+    let word_length = match form.selected(1)? {
+        0 => UartWordLength::Five,
+        1 => UartWordLength::Six,
+        2 => UartWordLength::Seven,
+        _ => UartWordLength::Eight,
+    };
+    let parity = match form.selected(2)? {
+        0 => UartParity::None,
+        1 => UartParity::Even,
+        _ => UartParity::Odd,
+    };
+
+    Some(UartSettings {
+        baud_rate: BAUD_RATES.get(form.selected(0)?)?.parse().ok()?,
+        word_length,
+        parity,
+    })
+}
+
+/// Returns the position of a word length in [`WORD_LENGTHS`]
+fn word_length_index(word_length: UartWordLength) -> usize {
+    match word_length {
+        UartWordLength::Five => 0,
+        UartWordLength::Six => 1,
+        UartWordLength::Seven => 2,
+        UartWordLength::Eight => 3,
+    }
+}
+
+/// Returns the position of a parity setting in [`PARITIES`]
+fn parity_index(parity: UartParity) -> usize {
+    match parity {
+        UartParity::None => 0,
+        UartParity::Even => 1,
+        UartParity::Odd => 2,
+    }
+}
+
+/// Applies a submitted UART form and returns to the main menu
+///
+/// A baud rate the UART cannot generate is reported at the prompt instead.
+fn finish_uart<IO: ByteIo>(terminal: &mut Terminal<IO>, form: Option<&Form>) {
+    if let Some(settings) = form.and_then(uart_settings_from_form)
+        && !terminal.set_uart_settings(settings)
+    {
+        cli_println!(
+            terminal,
+            error,
+            "setup: {} baud is not supported",
+            settings.baud_rate
+        );
+        terminal.set_exit_status(EXIT_FAILURE);
+        return;
+    }
+    show_main_menu(terminal, SECTION_UART);
+}
+
+/// Terminal settings edited by the terminal form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TerminalSettings {
+    /// Line ending expected from the remote terminal
+    input_line_ending: InputLineEnding,
+
+    /// Newline sequence written at the end of each output line
+    output_newline: OutputNewline,
+
+    /// Whether colours and text attributes are sent
+    styling: bool,
+
+    /// Number of columns of the terminal window
+    width: usize,
+
+    /// Number of rows of the terminal window, or `None` if output is not paged
+    height: Option<usize>,
+}
+
+impl TerminalSettings {
+    /// Reads the current settings of a terminal
+    fn read<IO: ByteIo>(terminal: &Terminal<IO>) -> Self {
+        TerminalSettings {
+            input_line_ending: terminal.input_line_ending(),
+            output_newline: terminal.output_newline(),
+            styling: terminal.styling(),
+            width: terminal.width(),
+            height: terminal.height(),
+        }
+    }
+
+    /// Builds the terminal form showing the settings
+    fn form(&self) -> Form {
+        let width = u32::try_from(self.width).unwrap_or(u32::MAX);
+        let height = u32::try_from(self.height.unwrap_or(VIEW_ROWS)).unwrap_or(u32::MAX);
+
+        Form::new("SETUP - Terminal")
+            .field(
+                "Line ending in",
+                Widget::Menu(Menu::new(
+                    INPUT_LINE_ENDINGS,
+                    input_line_ending_index(self.input_line_ending),
+                )),
+            )
+            .field(
+                "Newline out",
+                Widget::Menu(Menu::new(
+                    OUTPUT_NEWLINES,
+                    output_newline_index(self.output_newline),
+                )),
+            )
+            .field("Colours", Widget::Checkbox(Checkbox::new(self.styling)))
+            .field("Width", Widget::Number(NumberField::new(width, WIDTHS)))
+            .field(
+                "Paging",
+                Widget::Checkbox(Checkbox::new(self.height.is_some())),
+            )
+            .field("Height", Widget::Number(NumberField::new(height, HEIGHTS)))
+    }
+
+    /// Reads the settings from a submitted terminal form
+    ///
+    /// # Returns
+    ///
+    /// The settings, or `None` if the form is not a terminal form
+    fn from_form(form: &Form) -> Option<Self> {
+        let input_line_ending = match form.selected(0)? {
+            0 => InputLineEnding::Auto,
+            1 => InputLineEnding::Cr,
+            2 => InputLineEnding::Lf,
+            _ => InputLineEnding::CrLf,
+        };
+        let output_newline = match form.selected(1)? {
+            0 => OutputNewline::CrLf,
+            1 => OutputNewline::Lf,
+            _ => OutputNewline::Cr,
+        };
+        let height = form.number(5)? as usize;
+
+        Some(TerminalSettings {
+            input_line_ending,
+            output_newline,
+            styling: form.checked(2)?,
+            width: form.number(3)? as usize,
+            height: form.checked(4)?.then_some(height),
+        })
+    }
+
+    /// Applies the settings to a terminal
+    fn apply<IO: ByteIo>(&self, terminal: &mut Terminal<IO>) {
+        terminal.set_input_line_ending(self.input_line_ending);
+        terminal.set_output_newline(self.output_newline);
+        terminal.set_styling(self.styling);
+        terminal.set_width(self.width);
+        terminal.set_height(self.height);
+    }
+}
+
+/// Returns the position of an input line ending in [`INPUT_LINE_ENDINGS`]
+fn input_line_ending_index(line_ending: InputLineEnding) -> usize {
+    match line_ending {
+        InputLineEnding::Auto => 0,
+        InputLineEnding::Cr => 1,
+        InputLineEnding::Lf => 2,
+        InputLineEnding::CrLf => 3,
+    }
+}
+
+/// Returns the position of an output newline in [`OUTPUT_NEWLINES`]
+fn output_newline_index(newline: OutputNewline) -> usize {
+    match newline {
+        OutputNewline::CrLf => 0,
+        OutputNewline::Lf => 1,
+        OutputNewline::Cr => 2,
+    }
+}
+
+/// Applies a submitted terminal form and returns to the main menu
+fn finish_terminal<IO: ByteIo>(terminal: &mut Terminal<IO>, form: Option<&Form>) {
+    if let Some(settings) = form.and_then(TerminalSettings::from_form) {
+        settings.apply(terminal);
+    }
+    show_main_menu(terminal, SECTION_TERMINAL);
+}
+
+/// Builds the form choosing the pin to configure, starting at the onboard LED
+fn pins_form() -> Form {
+    let pins = 0..=(NUM_PINS - 1) as u32;
+    Form::new("SETUP - Pins").field(
+        "Pin",
+        Widget::Number(NumberField::new(ONBOARD_LED_NUM as u32, pins)),
+    )
+}
+
+/// Shows the mode form of the pin chosen in the pins form
+///
+/// Without GPIO, the error is reported at the prompt instead.
+fn finish_pins<IO: ByteIo>(terminal: &mut Terminal<IO>, form: Option<&Form>) {
+    let Some(pin) = form.and_then(|form| form.number(0)) else {
+        show_main_menu(terminal, SECTION_PINS);
+        return;
+    };
+    let pin = pin as usize;
+    match with_gpio(|gpio| (gpio.is_output(pin), gpio.read(pin))) {
+        Some((output, high)) => {
+            let form = pin_mode_form(pin, pin_mode_index(output, high));
+            terminal.show_form(form, finish_pin_mode::<IO>);
+        }
+        None => report_missing_gpio(terminal),
+    }
+}
+
+/// Returns the position in [`PIN_MODES`] of the mode a pin is in
+///
+/// # Arguments
+///
+/// * `output` - Whether the pin is configured as an output
+/// * `high` - Whether the pin reads high
+fn pin_mode_index(output: bool, high: bool) -> usize {
+    match (output, high) {
+        (false, _) => 0,
+        (true, false) => 1,
+        (true, true) => 2,
+    }
+}
+
+/// Builds the form setting the mode of a pin
+///
+/// The pin is the context of the form, shown in its title and read back by the handler.
+///
+/// # Arguments
+///
+/// * `pin` - Pin to configure
+/// * `mode` - Position in [`PIN_MODES`] of the mode the pin is in
+fn pin_mode_form(pin: usize, mode: usize) -> Form {
+    Form::new("SETUP - Pin")
+        .with_context(pin)
+        .field("Mode", Widget::Menu(Menu::new(PIN_MODES, mode)))
+}
+
+/// Configures the pin of a submitted pin mode form and returns to the main menu
+///
+/// Without GPIO, the error is reported at the prompt instead.
+fn finish_pin_mode<IO: ByteIo>(terminal: &mut Terminal<IO>, form: Option<&Form>) {
+    let pin_mode = form.and_then(|form| Some((form.context()?, form.selected(0)?)));
+    if let Some((pin, mode)) = pin_mode {
+        let installed = with_gpio(|gpio| match mode {
+            0 => gpio.set_input(pin),
+            _ => {
+                // Set the level first so the pin does not glitch when it starts driving
+                if mode == 2 {
+                    gpio.set_high(pin);
+                } else {
+                    gpio.set_low(pin);
+                }
+                gpio.set_output(pin);
+            }
+        });
+        if installed.is_none() {
+            report_missing_gpio(terminal);
+            return;
+        }
+    }
+    show_main_menu(terminal, SECTION_PINS);
+}
+
+/// Reports at the prompt that the pins cannot be configured
+fn report_missing_gpio<IO: ByteIo>(terminal: &mut Terminal<IO>) {
+    cli_println!(terminal, error, "setup: GPIO not initialised");
+    terminal.set_exit_status(EXIT_FAILURE);
+}
+
+/// Builds the prompt form
+///
+/// # Arguments
+///
+/// * `edit_mode` - Line editing mode selected at first
+fn prompt_form(edit_mode: EditMode) -> Form {
+    let edit_mode = match edit_mode {
+        EditMode::Emacs => 0,
+        EditMode::Vi => 1,
+    };
+    Form::new("SETUP - Prompt")
+        .field("Template", Widget::Menu(Menu::new(PROMPT_PRESETS, 0)))
+        .field(
+            "Editing keys",
+            Widget::Menu(Menu::new(EDIT_MODES, edit_mode)),
+        )
+}
+
+/// Applies a submitted prompt form and returns to the main menu
+fn finish_prompt<IO: ByteIo>(terminal: &mut Terminal<IO>, form: Option<&Form>) {
+    if let Some(form) = form {
+        match form.selected(0) {
+            Some(1) => terminal.reset_prompt_template(),
+            Some(preset) if preset > 1 => {
+                // The presets are shorter than the template limit, so they are always set
+                let _ = terminal.set_prompt_template(PROMPT_PRESETS[preset]);
+            }
+            _ => {}
+        }
+        match form.selected(1) {
+            Some(0) => terminal.set_edit_mode(EditMode::Emacs),
+            Some(_) => terminal.set_edit_mode(EditMode::Vi),
+            None => {}
+        }
+    }
+    show_main_menu(terminal, SECTION_PROMPT);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::uart::widgets::{FormEvent, FormKey};
+
+    fn settings() -> TerminalSettings {
+        TerminalSettings {
+            input_line_ending: InputLineEnding::Cr,
+            output_newline: OutputNewline::Lf,
+            styling: false,
+            width: 132,
+            height: None,
+        }
+    }
+
+    #[test]
+    fn terminal_form_round_trips_the_settings() {
+        assert_eq!(
+            TerminalSettings::from_form(&settings().form()),
+            Some(settings())
+        );
+        assert_eq!(TerminalSettings::from_form(&pins_form()), None);
+    }
+
+    #[test]
+    fn terminal_form_edits_fields_without_changing_the_menus_passed() {
+        let mut form = settings().form();
+
+        // Move across both menus, turn colours on, type a width of 100 and turn paging on
+        let keys = [
+            FormKey::Down,
+            FormKey::Down,
+            FormKey::Space,
+            FormKey::Down,
+            FormKey::Backspace,
+            FormKey::Backspace,
+            FormKey::Backspace,
+            FormKey::Digit(b'1'),
+            FormKey::Digit(b'0'),
+            FormKey::Digit(b'0'),
+            FormKey::Down,
+            FormKey::Space,
+        ];
+        for key in keys {
+            assert_eq!(form.handle(key), FormEvent::Editing);
+        }
+        let edited = TerminalSettings {
+            styling: true,
+            width: 100,
+            height: Some(VIEW_ROWS),
+            ..settings()
+        };
+        assert_eq!(TerminalSettings::from_form(&form), Some(edited));
+
+        // Going back up leaves the menus alone too; Right changes the focused one
+        for _ in 0..4 {
+            form.handle(FormKey::Up);
+        }
+        assert_eq!(TerminalSettings::from_form(&form), Some(edited));
+        form.handle(FormKey::Right);
+        assert_eq!(form.handle(FormKey::Enter), FormEvent::Submitted);
+        assert_eq!(
+            TerminalSettings::from_form(&form),
+            Some(TerminalSettings {
+                input_line_ending: InputLineEnding::Lf,
+                ..edited
+            })
+        );
+    }
+
+    #[test]
+    fn uart_form_round_trips_the_settings() {
+        let settings = UartSettings {
+            baud_rate: 9600,
+            word_length: UartWordLength::Seven,
+            parity: UartParity::Even,
+        };
+        assert_eq!(
+            uart_settings_from_form(&uart_form(&settings)),
+            Some(settings)
+        );
+
+        // A rate that is not offered falls back to the default
+        let unusual = UartSettings {
+            baud_rate: 12345,
+            ..UartSettings::new()
+        };
+        assert_eq!(
+            uart_settings_from_form(&uart_form(&unusual)),
+            Some(UartSettings::new())
+        );
+        assert_eq!(uart_settings_from_form(&pins_form()), None);
+    }
+
+    #[test]
+    fn pin_mode_form_starts_at_the_current_mode() {
+        let form = pin_mode_form(25, pin_mode_index(true, true));
+        assert_eq!(form.context(), Some(25));
+        assert_eq!(form.selected(0), Some(2));
+        assert_eq!(pin_mode_index(false, true), 0);
+        assert_eq!(pin_mode_index(true, false), 1);
+    }
+
+    #[test]
+    fn prompt_form_starts_at_the_current_edit_mode() {
+        let form = prompt_form(EditMode::Vi);
+        assert_eq!(form.selected(0), Some(0));
+        assert_eq!(form.selected(1), Some(1));
+    }
+}
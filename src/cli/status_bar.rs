@@ -0,0 +1,60 @@
+//! Status bar shown by the `statusbar` command
+//!
+//! The bar stays pinned to the top or bottom row while command output scrolls beneath
+//! it, see [`Terminal::show_status_bar`]. It shows the state of the onboard LED and the
+//! uptime.
+use crate::constants::ONBOARD_LED_NUM;
+use crate::peripherals::gpio::with_gpio;
+use crate::peripherals::timer::uptime_us;
+use crate::peripherals::uart::ByteIo;
+use crate::peripherals::uart::prompt::write_uptime;
+use crate::peripherals::uart::status_bar::StatusText;
+use crate::peripherals::uart::terminal::Terminal;
+use core::fmt::Write;
+
+/// Writes the status bar text for the current board state, see
+/// [`Terminal::show_status_bar`]
+pub fn draw<IO: ByteIo>(_terminal: &Terminal<IO>, text: &mut StatusText) {
+    let led = with_gpio(|gpio| gpio.read(ONBOARD_LED_NUM));
+    render(led, uptime_us(), text);
+}
+
+/// Writes the status bar text
+///
+/// # Arguments
+///
+/// * `led` - Whether the onboard LED is lit, if the GPIO pins can be read
+/// * `uptime_us` - Time since start-up in microseconds, if known
+/// * `text` - Empty text to write to
+fn render(led: Option<bool>, uptime_us: Option<u64>, text: &mut StatusText) {
+    let _ = text.write_str(" PICO   LED ");
+    let _ = text.write_str(match led {
+        Some(true) => "on",
+        Some(false) => "off",
+        None => "-",
+    });
+
+    let _ = text.write_str("   up ");
+    match uptime_us {
+        Some(uptime_us) => write_uptime(text, uptime_us),
+        None => {
+            let _ = text.write_str("-");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_led_and_uptime() {
+        let mut text = StatusText::new();
+        render(Some(true), Some(61_000_000), &mut text);
+        assert_eq!(text, " PICO   LED on   up 00:01:01");
+
+        let mut text = StatusText::new();
+        render(None, None, &mut text);
+        assert_eq!(text, " PICO   LED -   up -");
+    }
+}
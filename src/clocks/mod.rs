@@ -3,10 +3,48 @@
 //! This module provides functionality for initializing and managing the system clocks
 //! on the RP2040 microcontroller.
 
+use core::cell::Cell;
+use cortex_m::interrupt::{Mutex, free};
 use rp_pico::XOSC_CRYSTAL_FREQ;
 use rp2040_hal::clocks::{ClocksManager, init_clocks_and_plls};
 use rp2040_hal::{Clock, Watchdog, pac};
 
+/// Clock frequencies published by [`ClockAPI::publish`] for code that does not own the
+/// clocks manager
+static FREQUENCIES: Mutex<Cell<Option<ClockFrequencies>>> = Mutex::new(Cell::new(None));
+
+/// Frequencies in Hz of the clocks generated by the clocks block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockFrequencies {
+    /// System clock, driving the processors and bus fabric
+    pub system: u32,
+
+    /// Reference clock, used by the watchdog and timer
+    pub reference: u32,
+
+    /// Peripheral clock, used by UART and SPI
+    pub peripheral: u32,
+
+    /// USB clock
+    pub usb: u32,
+
+    /// ADC clock
+    pub adc: u32,
+
+    /// RTC clock
+    pub rtc: u32,
+}
+
+/// Returns the clock frequencies published by [`ClockAPI::publish`]
+///
+/// # Returns
+///
+/// The frequencies, or `None` if they have not been published yet
+pub fn frequencies() -> Option<ClockFrequencies> {
+    // Enter interrupt-free section
+    free(|cs| FREQUENCIES.borrow(cs).get())
+}
+
 /// Manages the clocks configuration for the RP2040 microcontroller.
 ///
 /// This struct encapsulates the clocks manager and provides methods for
@@ -63,4 +101,22 @@ impl ClockAPI {
     pub fn uart_clock_freq(&self) -> u32 {
         self.clocks.peripheral_clock.freq().to_Hz()
     }
+
+    /// Returns the frequencies the clocks are running at
+    pub fn frequencies(&self) -> ClockFrequencies {
+        ClockFrequencies {
+            system: self.clocks.system_clock.freq().to_Hz(),
+            reference: self.clocks.reference_clock.freq().to_Hz(),
+            peripheral: self.clocks.peripheral_clock.freq().to_Hz(),
+            usb: self.clocks.usb_clock.freq().to_Hz(),
+            adc: self.clocks.adc_clock.freq().to_Hz(),
+            rtc: self.clocks.rtc_clock.freq().to_Hz(),
+        }
+    }
+
+    /// Publishes the clock frequencies so they can be read through [`frequencies`]
+    pub fn publish(&self) {
+        let frequencies = self.frequencies();
+        free(|cs| FREQUENCIES.borrow(cs).set(Some(frequencies)));
+    }
 }
@@ -1,2 +1,67 @@
 /// Maximum number of UART input characters accepted on a single line in the terminal
 pub const MAX_LINE_LENGTH: usize = 128;
+
+/// Size in bytes of the UART receive queue
+///
+/// Independent of the line length so that pasted multi-line scripts are received whole.
+pub const RX_BUFFER_SIZE: usize = 1024;
+
+/// Size in bytes of the command output held back while paged output waits at the
+/// `--More--` prompt; a command writing more waits for the user's key
+pub const PAGED_OUTPUT_SIZE: usize = 2048;
+
+/// Maximum number of characters that separate words for word motion and deletion
+pub const MAX_WORD_SEPARATORS: usize = 16;
+
+/// Number of columns assumed for the terminal window until its width is known
+pub const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+/// Time in microseconds after which an ESC without a following byte is taken as the Esc
+/// key in vi mode, rather than the start of a key sequence
+pub const ESCAPE_TIMEOUT_US: u64 = 50_000;
+
+/// Number of rows of a full-screen view; rows beyond the terminal height are not drawn
+pub const VIEW_ROWS: usize = 24;
+
+/// Number of columns of a full-screen view; columns beyond the terminal width are not drawn
+pub const VIEW_COLUMNS: usize = 80;
+
+/// Interval in microseconds between redraws of a full-screen view
+pub const VIEW_REFRESH_US: u64 = 250_000;
+
+/// Interval in microseconds between updates of the status bar
+pub const STATUS_REFRESH_US: u64 = 1_000_000;
+
+/// Time in microseconds the terminal is given to answer a window size query; later
+/// replies are not taken as the size
+pub const SIZE_QUERY_TIMEOUT_US: u64 = 500_000;
+
+/// Maximum number of fields of a form shown with the terminal widgets
+pub const MAX_FORM_FIELDS: usize = 8;
+
+/// Maximum length in bytes of a prompt template set from the shell
+pub const PROMPT_TEMPLATE_LENGTH: usize = 64;
+
+/// Maximum length in bytes of the context text shown in the prompt
+pub const PROMPT_CONTEXT_LENGTH: usize = 16;
+
+/// Number of submitted lines kept in the terminal command history
+pub const HISTORY_CAPACITY: usize = 16;
+
+/// Number of edits of the current line that can be undone
+pub const UNDO_DEPTH: usize = 16;
+
+/// Number of killed (cut) texts kept for yanking
+pub const KILL_RING_CAPACITY: usize = 4;
+
+/// Maximum number of whitespace-separated arguments accepted by a CLI command
+pub const MAX_COMMAND_ARGS: usize = 8;
+
+/// GPIO pin number for the onboard LED
+pub const ONBOARD_LED_NUM: usize = 25;
+
+/// Maximum number of candidates offered by tab completion
+pub const MAX_COMPLETION_CANDIDATES: usize = 32;
+
+/// Size in bytes of the queue holding log messages waiting to be printed above the prompt
+pub const LOG_QUEUE_SIZE: usize = 256;
@@ -2,34 +2,48 @@
 //!
 //! This application runs on the Raspberry Pi Pico RP2040 Microcontroller
 //!
-#![no_std]
-#![no_main]
+//! Building with the `sim` feature (`cargo sim`) instead runs the shell on the build
+//! machine against simulated peripherals, see the `sim` module.
+//!
+#![cfg_attr(not(any(test, feature = "sim")), no_std)]
+#![cfg_attr(not(any(test, feature = "sim")), no_main)]
 
 mod cli;
+#[cfg_attr(feature = "sim", path = "sim/clocks.rs")]
 mod clocks;
 mod constants;
 mod peripherals;
-
-use crate::cli::Cli;
-use crate::clocks::ClockAPI;
-use crate::peripherals::gpio::Gpio;
-use rp2040_hal::{Watchdog, entry};
-use rp2040_pac::Peripherals;
-
-/// GPIO pin number for the onboard LED
-const ONBOARD_LED_NUM: usize = 25;
+#[cfg(feature = "sim")]
+mod sim;
 
 /// Panic handler that loops indefinitely
+#[cfg(not(any(test, feature = "sim")))]
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
     loop {}
 }
 
 /// Main entry point for the application
-///
-/// Initializes the system and enters the main loop
-#[entry]
+#[cfg(not(any(test, feature = "sim")))]
+#[rp2040_hal::entry]
 fn _start() -> ! {
+    run()
+}
+
+/// Initializes the system and enters the main loop
+// Host test builds have no entry point; keeping the firmware reachable from here leaves
+// only code it does not use reported as dead
+#[cfg(not(feature = "sim"))]
+#[cfg_attr(test, allow(dead_code))]
+fn run() -> ! {
+    use crate::cli::Cli;
+    use crate::clocks::ClockAPI;
+    use crate::constants::ONBOARD_LED_NUM;
+    use crate::peripherals::gpio::Gpio;
+    use crate::peripherals::timer::Timer;
+    use rp2040_hal::Watchdog;
+    use rp2040_pac::Peripherals;
+
     unsafe { cortex_m::interrupt::enable() };
 
     // This object is used to access peripherals such as GPIO and reset registers
@@ -48,6 +62,9 @@ fn _start() -> ! {
         &mut watchdog,
     );
 
+    // Start counting the uptime shown in the prompt
+    Timer::new(peripherals.TIMER, &mut peripherals.RESETS).install();
+
     // Initialize GPIO pins
     let mut pins = Gpio::new(
         peripherals.SIO,
@@ -61,7 +78,7 @@ fn _start() -> ! {
     pins.set_function(0, 0b010);
     pins.set_function(1, 0b010);
 
-    let mut cli: Cli = Cli::new(
+    let mut cli = Cli::new(
         peripherals.UART0,
         &mut peripherals.RESETS,
         clocks.uart_clock_freq(),
@@ -69,7 +86,22 @@ fn _start() -> ! {
 
     pins.set_high(ONBOARD_LED_NUM);
 
+    // Make the clock frequencies available to the dashboard
+    clocks.publish();
+
+    // Hand the pins over to the CLI command handlers
+    pins.install();
+
     loop {
         cli.process_input();
     }
 }
+
+/// Host entry point for the simulator build
+///
+/// Runs the shell against simulated peripherals on the build machine
+#[cfg(feature = "sim")]
+#[cfg_attr(test, allow(dead_code))]
+fn main() {
+    sim::run();
+}
@@ -3,11 +3,32 @@
 //! This module provides a safe interface for controlling GPIO pins
 //! on the RP2040 microcontroller.
 
+use core::cell::RefCell;
+use cortex_m::interrupt::{Mutex, free};
 use rp2040_pac::io_bank0::gpio::gpio_ctrl::FUNCSEL_A::SIO as SIOFuncSel;
 use rp2040_pac::{IO_BANK0, PADS_BANK0, RESETS, SIO};
 
 /// Maximum number of GPIO pins available on the RP2040
-const NUM_PINS: usize = 30;
+pub const NUM_PINS: usize = 30;
+
+/// Global GPIO instance shared with the CLI command handlers
+///
+/// Populated once by [`Gpio::install`] after initialisation in `main`
+static GPIO: Mutex<RefCell<Option<Gpio>>> = Mutex::new(RefCell::new(None));
+
+/// Runs a closure with exclusive access to the global GPIO instance
+///
+/// # Arguments
+///
+/// * `f` - Closure receiving the installed `Gpio`
+///
+/// # Returns
+///
+/// The closure result, or `None` if no `Gpio` has been installed yet
+pub fn with_gpio<R>(f: impl FnOnce(&mut Gpio) -> R) -> Option<R> {
+    // Enter interrupt-free section
+    free(|cs| GPIO.borrow(cs).borrow_mut().as_mut().map(f))
+}
 
 /// Manages GPIO operations for the RP2040 microcontroller.
 ///
@@ -76,6 +97,13 @@ impl Gpio {
         }
     }
 
+    /// Moves this instance into global storage so it can be reached through [`with_gpio`]
+    pub fn install(self) {
+        free(|cs| {
+            GPIO.borrow(cs).replace(Some(self));
+        });
+    }
+
     /// Sets a GPIO pin to high state.
     ///
     /// # Arguments
@@ -130,7 +158,34 @@ impl Gpio {
     ///
     /// Returns `true` if the pin is high, `false` if the pin is low
     pub fn read(&mut self, pin_num: usize) -> bool {
-        todo!()
+        self.sio.gpio_in().read().bits() & (1 << pin_num as u32) != 0
+    }
+
+    /// Returns the function selected for a GPIO pin.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin_num` - The GPIO pin number (0-29)
+    ///
+    /// # Returns
+    ///
+    /// The `FUNCSEL` value of the pin, e.g. 5 for SIO
+    pub fn function(&self, pin_num: usize) -> u8 {
+        self.io_bank0
+            .gpio(pin_num)
+            .gpio_ctrl()
+            .read()
+            .funcsel()
+            .bits()
+    }
+
+    /// Returns whether a GPIO pin is configured as an output.
+    ///
+    /// # Arguments
+    ///
+    /// * `pin_num` - The GPIO pin number (0-29)
+    pub fn is_output(&self, pin_num: usize) -> bool {
+        self.sio.gpio_oe().read().bits() & (1 << pin_num as u32) != 0
     }
 
     pub fn set_function(&mut self, pin_num: usize, func: u8) {
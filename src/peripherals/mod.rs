@@ -1,2 +1,5 @@
+#[cfg_attr(feature = "sim", path = "../sim/gpio.rs")]
 pub mod gpio;
+#[cfg_attr(feature = "sim", path = "../sim/timer.rs")]
+pub mod timer;
 pub mod uart;
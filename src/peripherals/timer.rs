@@ -0,0 +1,67 @@
+//! Uptime timer module.
+//!
+//! The RP2040 TIMER peripheral counts microseconds from the watchdog tick, which the
+//! clock setup starts. Once [`Timer::install`] has been called, the count is available
+//! anywhere, including interrupt handlers, through [`uptime_us`].
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use rp2040_pac::{RESETS, TIMER};
+
+/// Set by [`Timer::install`] once the counter is running
+///
+/// Reading the counter registers has no side effects, so no lock is needed once the
+/// peripheral is out of reset; the flag only guards against reading it before that.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Microsecond counter of the RP2040
+pub struct Timer {
+    /// The TIMER peripheral
+    _timer: TIMER,
+}
+
+impl Timer {
+    /// Takes the TIMER peripheral out of reset
+    ///
+    /// # Arguments
+    ///
+    /// * `timer` - The TIMER peripheral
+    /// * `resets` - The reset controller
+    ///
+    /// # Returns
+    ///
+    /// A new `Timer` instance
+    pub fn new(timer: TIMER, resets: &mut RESETS) -> Self {
+        resets.reset().modify(|_, w| w.timer().clear_bit());
+        while resets.reset_done().read().timer().bit_is_clear() {}
+
+        Timer { _timer: timer }
+    }
+
+    /// Makes the counter available through [`uptime_us`]
+    pub fn install(self) {
+        INSTALLED.store(true, Ordering::Release);
+    }
+}
+
+/// Returns the time since the timer was taken out of reset
+///
+/// # Returns
+///
+/// The uptime in microseconds, or `None` if no `Timer` has been installed yet
+pub fn uptime_us() -> Option<u64> {
+    if !INSTALLED.load(Ordering::Acquire) {
+        return None;
+    }
+
+    // SAFETY: the counter registers are read-only and reading them has no side effects
+    let timer = unsafe { &*TIMER::ptr() };
+
+    // Re-read the high word in case the low word wrapped between the two reads
+    loop {
+        let high = timer.timerawh().read().bits();
+        let low = timer.timerawl().read().bits();
+        if timer.timerawh().read().bits() == high {
+            return Some((u64::from(high) << 32) | u64::from(low));
+        }
+    }
+}
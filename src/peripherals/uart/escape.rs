@@ -0,0 +1,492 @@
+//! Escape sequence parser module for terminal input.
+//!
+//! This module implements a byte-at-a-time parser for the VT100/ECMA-48 escape
+//! sequences sent by terminal emulators:
+//! - CSI sequences (`ESC [` parameters, intermediates, final byte)
+//! - SS3 sequences (`ESC O` final byte)
+//! - Plain two-byte escapes (`ESC` followed by a character, e.g. Alt-key combinations)
+//!
+//! Complete sequences are decoded into [`KeyEvent`]s the line editor can act on.
+
+/// Maximum number of numeric parameters collected for a single CSI sequence
+const MAX_PARAMS: usize = 4;
+
+/// Maximum number of intermediate bytes collected for a single CSI sequence
+const MAX_INTERMEDIATES: usize = 2;
+
+/// Byte values with special meaning while parsing escape sequences
+enum SequenceByte {
+    Escape = 0x1B,
+    LeftBracket = 0x5B,
+    LetterO = 0x4F,
+    Semicolon = 0x3B,
+    Backspace = 0x08,
+    Delete = 0x7F,
+}
+
+/// Introducer of a complete escape sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceKind {
+    /// `ESC` followed by a single character
+    Escape,
+    /// Control Sequence Introducer, `ESC [`
+    Csi,
+    /// Single Shift Three, `ESC O`
+    Ss3,
+}
+
+/// A complete escape sequence as received from the terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscapeSequence {
+    /// Introducer of the sequence
+    pub kind: SequenceKind,
+
+    /// Private marker (`<`, `=`, `>` or `?`) leading the parameters, if any
+    pub private_marker: Option<u8>,
+
+    /// Numeric parameters; omitted parameters are recorded as 0
+    pub params: heapless::Vec<u16, MAX_PARAMS>,
+
+    /// Intermediate bytes (0x20..=0x2F) preceding the final byte
+    pub intermediates: heapless::Vec<u8, MAX_INTERMEDIATES>,
+
+    /// Final byte terminating the sequence
+    pub final_byte: u8,
+}
+
+impl EscapeSequence {
+    /// Returns the parameter at `index`, treating missing or zero values as `default`
+    pub fn param(&self, index: usize, default: u16) -> u16 {
+        match self.params.get(index) {
+            Some(&value) if value != 0 => value,
+            _ => default,
+        }
+    }
+}
+
+/// Keys that are reported through escape sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Right,
+    Left,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    /// `ESC` followed by an ordinary character, i.e. Alt/Meta plus that character
+    ///
+    /// Alt-Backspace is reported as `Meta` with the terminal's backspace byte (DEL or BS).
+    Meta(u8),
+    /// Start of pasted text in bracketed paste mode (`ESC [ 200 ~`)
+    PasteStart,
+    /// End of pasted text in bracketed paste mode (`ESC [ 201 ~`)
+    PasteEnd,
+}
+
+/// Modifier keys held while a key was pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+}
+
+impl Modifiers {
+    /// Decodes an xterm modifier parameter (1 + bitmask of shift=1, alt=2, ctrl=4)
+    fn from_param(param: u16) -> Self {
+        let mask = param.saturating_sub(1);
+        Modifiers {
+            shift: mask & 0b001 != 0,
+            alt: mask & 0b010 != 0,
+            ctrl: mask & 0b100 != 0,
+        }
+    }
+}
+
+/// A decoded key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl KeyEvent {
+    /// Decodes a complete escape sequence into a key press
+    ///
+    /// # Returns
+    ///
+    /// The key press, or `None` if the sequence does not describe a known key
+    pub fn from_sequence(sequence: &EscapeSequence) -> Option<Self> {
+        let (key, modifier_param) = match sequence.kind {
+            SequenceKind::Escape => (Key::Meta(sequence.final_byte), 1),
+            SequenceKind::Ss3 => (Self::cursor_key(sequence.final_byte)?, 1),
+            SequenceKind::Csi => {
+                if sequence.private_marker.is_some() || !sequence.intermediates.is_empty() {
+                    return None;
+                }
+
+                match sequence.final_byte {
+                    b'~' => {
+                        let key = match sequence.param(0, 0) {
+                            1 | 7 => Key::Home,
+                            2 => Key::Insert,
+                            3 => Key::Delete,
+                            4 | 8 => Key::End,
+                            5 => Key::PageUp,
+                            6 => Key::PageDown,
+                            200 => Key::PasteStart,
+                            201 => Key::PasteEnd,
+                            _ => return None,
+                        };
+                        (key, sequence.param(1, 1))
+                    }
+                    final_byte => (Self::cursor_key(final_byte)?, sequence.param(1, 1)),
+                }
+            }
+        };
+
+        Some(KeyEvent {
+            key,
+            modifiers: Modifiers::from_param(modifier_param),
+        })
+    }
+
+    /// Maps the final byte of a cursor-key sequence (CSI or SS3) to its key
+    fn cursor_key(final_byte: u8) -> Option<Key> {
+        match final_byte {
+            b'A' => Some(Key::Up),
+            b'B' => Some(Key::Down),
+            b'C' => Some(Key::Right),
+            b'D' => Some(Key::Left),
+            b'H' => Some(Key::Home),
+            b'F' => Some(Key::End),
+            _ => None,
+        }
+    }
+}
+
+/// Represents the state of escape sequence processing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParserState {
+    /// No escape sequence in progress
+    Ground,
+    /// ESC received
+    Escape,
+    /// ESC [ received, collecting parameter bytes
+    CsiParam,
+    /// Collecting CSI intermediate bytes
+    CsiIntermediate,
+    /// ESC O received, waiting for the final byte
+    Ss3,
+}
+
+/// Outcome of feeding one byte to the [`EscapeParser`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseResult {
+    /// The byte was consumed and the sequence is not complete yet
+    Pending,
+    /// The byte completed a sequence
+    Complete(EscapeSequence),
+    /// The byte completed a malformed sequence, which was consumed and dropped
+    Discarded,
+    /// The byte cannot be part of a sequence; the partial sequence was discarded
+    /// and the byte should be handled as ordinary input
+    Rejected,
+}
+
+/// Incremental escape sequence parser
+pub struct EscapeParser {
+    /// Current parser state
+    state: ParserState,
+
+    /// Sequence collected so far
+    sequence: EscapeSequence,
+
+    /// Whether a digit or separator has opened the parameter currently being collected
+    param_started: bool,
+
+    /// Whether more parameters were received than can be stored
+    params_overflowed: bool,
+
+    /// Whether the sequence being collected is malformed and must be dropped
+    malformed: bool,
+}
+
+impl EscapeParser {
+    /// Creates a parser in the ground state
+    pub fn new() -> Self {
+        EscapeParser {
+            state: ParserState::Ground,
+            sequence: EscapeSequence {
+                kind: SequenceKind::Escape,
+                private_marker: None,
+                params: heapless::Vec::new(),
+                intermediates: heapless::Vec::new(),
+                final_byte: 0,
+            },
+            param_started: false,
+            params_overflowed: false,
+            malformed: false,
+        }
+    }
+
+    /// Abandons any partially received sequence
+    pub fn reset(&mut self) {
+        self.state = ParserState::Ground;
+    }
+
+    /// Returns whether an ESC was received and nothing after it yet
+    ///
+    /// Until the next byte arrives, the ESC may be the Esc key or the start of a sequence.
+    pub fn is_escape_pending(&self) -> bool {
+        self.state == ParserState::Escape
+    }
+
+    /// Feeds one input byte to the parser
+    ///
+    /// In the ground state only ESC is consumed; any other byte is rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte` - The received byte
+    ///
+    /// # Returns
+    ///
+    /// Whether the byte was consumed, completed a sequence or must be handled by the caller
+    pub fn feed(&mut self, byte: u8) -> ParseResult {
+        match self.state {
+            ParserState::Ground => {
+                if byte == SequenceByte::Escape as u8 {
+                    self.begin();
+                    ParseResult::Pending
+                } else {
+                    ParseResult::Rejected
+                }
+            }
+
+            ParserState::Escape => match byte {
+                x if x == SequenceByte::LeftBracket as u8 => {
+                    self.sequence.kind = SequenceKind::Csi;
+                    self.state = ParserState::CsiParam;
+                    ParseResult::Pending
+                }
+                x if x == SequenceByte::LetterO as u8 => {
+                    self.sequence.kind = SequenceKind::Ss3;
+                    self.state = ParserState::Ss3;
+                    ParseResult::Pending
+                }
+                x if x == SequenceByte::Escape as u8 => {
+                    // A repeated ESC restarts the sequence
+                    self.begin();
+                    ParseResult::Pending
+                }
+                0x20..=0x7E => self.finish(byte),
+                x if x == SequenceByte::Delete as u8 || x == SequenceByte::Backspace as u8 => {
+                    self.finish(byte)
+                }
+                _ => self.reject(),
+            },
+
+            ParserState::CsiParam => match byte {
+                b'0'..=b'9' => {
+                    if !self.param_started {
+                        self.start_param();
+                    }
+                    if !self.params_overflowed
+                        && let Some(value) = self.sequence.params.last_mut()
+                    {
+                        *value = value
+                            .saturating_mul(10)
+                            .saturating_add((byte - b'0') as u16);
+                    }
+                    ParseResult::Pending
+                }
+                x if x == SequenceByte::Semicolon as u8 || x == b':' => {
+                    if !self.param_started {
+                        self.start_param();
+                    }
+                    self.param_started = false;
+                    ParseResult::Pending
+                }
+                b'<'..=b'?' => {
+                    let at_start = self.sequence.params.is_empty() && !self.param_started;
+                    if at_start && self.sequence.private_marker.is_none() {
+                        self.sequence.private_marker = Some(byte);
+                    } else {
+                        self.malformed = true;
+                    }
+                    ParseResult::Pending
+                }
+                0x20..=0x2F => {
+                    self.state = ParserState::CsiIntermediate;
+                    self.collect_intermediate(byte)
+                }
+                0x40..=0x7E => self.finish(byte),
+                _ => self.reject(),
+            },
+
+            ParserState::CsiIntermediate => match byte {
+                0x20..=0x2F => self.collect_intermediate(byte),
+                0x30..=0x3F => {
+                    // Parameter bytes are not allowed after intermediates
+                    self.malformed = true;
+                    ParseResult::Pending
+                }
+                0x40..=0x7E => self.finish(byte),
+                _ => self.reject(),
+            },
+
+            ParserState::Ss3 => match byte {
+                0x40..=0x7E => self.finish(byte),
+                _ => self.reject(),
+            },
+        }
+    }
+
+    /// Starts collecting a new sequence after an ESC byte
+    fn begin(&mut self) {
+        self.state = ParserState::Escape;
+        self.sequence.kind = SequenceKind::Escape;
+        self.sequence.private_marker = None;
+        self.sequence.params.clear();
+        self.sequence.intermediates.clear();
+        self.sequence.final_byte = 0;
+        self.param_started = false;
+        self.params_overflowed = false;
+        self.malformed = false;
+    }
+
+    /// Opens a new, initially omitted (zero) parameter
+    fn start_param(&mut self) {
+        self.param_started = true;
+        if self.sequence.params.push(0).is_err() {
+            self.params_overflowed = true;
+        }
+    }
+
+    /// Records an intermediate byte
+    ///
+    /// Bytes beyond the intermediate capacity are still consumed so the rest of the
+    /// sequence does not leak into the line, but the sequence is dropped once complete.
+    fn collect_intermediate(&mut self, byte: u8) -> ParseResult {
+        if self.sequence.intermediates.push(byte).is_err() {
+            self.malformed = true;
+        }
+        ParseResult::Pending
+    }
+
+    /// Discards the partial sequence and hands the current byte back to the caller
+    fn reject(&mut self) -> ParseResult {
+        self.reset();
+        ParseResult::Rejected
+    }
+
+    /// Completes the current sequence with its final byte
+    fn finish(&mut self, final_byte: u8) -> ParseResult {
+        self.state = ParserState::Ground;
+        if self.malformed {
+            return ParseResult::Discarded;
+        }
+
+        // A trailing separator (e.g. `ESC [ 1 ; C`) denotes an omitted last parameter
+        if self.sequence.kind == SequenceKind::Csi
+            && !self.param_started
+            && !self.sequence.params.is_empty()
+        {
+            self.start_param();
+        }
+
+        self.sequence.final_byte = final_byte;
+        ParseResult::Complete(self.sequence.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds bytes to a fresh parser and returns the last result
+    fn parse(bytes: &[u8]) -> ParseResult {
+        let mut parser = EscapeParser::new();
+        let mut result = ParseResult::Rejected;
+        for &byte in bytes {
+            result = parser.feed(byte);
+        }
+        result
+    }
+
+    /// Parses a complete sequence and decodes it into a key press
+    fn key(bytes: &[u8]) -> Option<KeyEvent> {
+        match parse(bytes) {
+            ParseResult::Complete(sequence) => KeyEvent::from_sequence(&sequence),
+            other => panic!("incomplete sequence: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn decodes_tilde_sequences() {
+        assert_eq!(key(b"\x1b[3~").map(|event| event.key), Some(Key::Delete));
+        assert_eq!(key(b"\x1b[1~").map(|event| event.key), Some(Key::Home));
+        assert_eq!(key(b"\x1b[4~").map(|event| event.key), Some(Key::End));
+        assert_eq!(
+            key(b"\x1b[200~").map(|event| event.key),
+            Some(Key::PasteStart)
+        );
+        assert_eq!(
+            key(b"\x1b[201~").map(|event| event.key),
+            Some(Key::PasteEnd)
+        );
+    }
+
+    #[test]
+    fn decodes_modifier_parameter() {
+        let event = key(b"\x1b[1;5C").unwrap();
+        assert_eq!(event.key, Key::Right);
+        assert!(event.modifiers.ctrl);
+        assert!(!event.modifiers.shift);
+    }
+
+    #[test]
+    fn decodes_ss3_and_meta_keys() {
+        assert_eq!(key(b"\x1bOH").map(|event| event.key), Some(Key::Home));
+        assert_eq!(key(b"\x1bb").map(|event| event.key), Some(Key::Meta(b'b')));
+        assert_eq!(
+            key(b"\x1b\x7f").map(|event| event.key),
+            Some(Key::Meta(0x7F))
+        );
+    }
+
+    #[test]
+    fn collects_private_marker_and_params() {
+        let ParseResult::Complete(sequence) = parse(b"\x1b[?2004h") else {
+            panic!("sequence not complete");
+        };
+        assert_eq!(sequence.private_marker, Some(b'?'));
+        assert_eq!(sequence.params.as_slice(), &[2004]);
+        assert_eq!(sequence.final_byte, b'h');
+        assert_eq!(KeyEvent::from_sequence(&sequence), None);
+    }
+
+    #[test]
+    fn control_byte_aborts_sequence() {
+        assert_eq!(parse(b"\x1b[1\r"), ParseResult::Rejected);
+    }
+
+    #[test]
+    fn lone_escape_is_pending_until_the_next_byte() {
+        let mut parser = EscapeParser::new();
+        parser.feed(0x1B);
+        assert!(parser.is_escape_pending());
+        parser.feed(b'[');
+        assert!(!parser.is_escape_pending());
+    }
+
+    #[test]
+    fn excess_intermediates_are_discarded() {
+        assert_eq!(parse(b"\x1b[   A"), ParseResult::Discarded);
+    }
+}
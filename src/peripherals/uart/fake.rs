@@ -0,0 +1,250 @@
+//! In-memory transport and test terminal for host tests.
+//!
+//! Records every byte the terminal writes so tests can assert on the exact
+//! transcript, and hands queued keystrokes back through [`ByteIo::get_input`].
+//! The terminal created by [`terminal`] runs a small command set exercising line
+//! requests, views, forms and paged output, shared by the tests of the modules
+//! implementing them.
+
+use crate::cli::commands::Command;
+use crate::constants::{LOG_QUEUE_SIZE, RX_BUFFER_SIZE};
+use crate::peripherals::uart::ByteIo;
+use crate::peripherals::uart::print::cli_println;
+use crate::peripherals::uart::screen::Frame;
+use crate::peripherals::uart::status_bar::StatusText;
+use crate::peripherals::uart::style::{Color, Style};
+use crate::peripherals::uart::terminal::{Echo, Terminal};
+use crate::peripherals::uart::widgets::{Form, Menu, NumberField, Widget};
+use core::fmt;
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+/// Fake byte transport backed by in-memory buffers
+#[derive(Default)]
+pub struct FakeIo {
+    /// Bytes written by the terminal, in order
+    pub output: Vec<u8>,
+
+    /// Bytes waiting to be read by the terminal
+    pub input: VecDeque<u8>,
+
+    /// Log messages waiting to be printed above the prompt
+    pub log: Vec<u8>,
+
+    /// Number of input bytes to report as lost on the next check
+    pub dropped_input: usize,
+
+    /// UART index to report, if any
+    pub uart_index: Option<u8>,
+}
+
+impl FakeIo {
+    /// Queues keystrokes to be returned by the next `get_input` calls
+    pub fn type_bytes(&mut self, bytes: &[u8]) {
+        self.input.extend(bytes);
+    }
+
+    /// Queues a log message to be returned by the next `get_log` call
+    pub fn log_message(&mut self, message: &[u8]) {
+        self.log.extend_from_slice(message);
+        self.log.push(b'\n');
+    }
+
+    /// Returns the transcript written so far and clears it
+    pub fn take_output(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.output)
+    }
+}
+
+impl ByteIo for FakeIo {
+    fn putc(&mut self, c: u8) {
+        self.output.push(c);
+    }
+
+    fn get_input(&mut self) -> heapless::Vec<u8, RX_BUFFER_SIZE> {
+        let mut buffer = heapless::Vec::new();
+        while !buffer.is_full() {
+            match self.input.pop_front() {
+                Some(byte) => buffer.push(byte).unwrap(),
+                None => break,
+            }
+        }
+        buffer
+    }
+
+    fn take_dropped_input(&mut self) -> usize {
+        core::mem::take(&mut self.dropped_input)
+    }
+
+    fn get_log(&mut self) -> heapless::Vec<u8, LOG_QUEUE_SIZE> {
+        let log = core::mem::take(&mut self.log);
+        heapless::Vec::from_slice(&log).unwrap()
+    }
+
+    fn uart_index(&self) -> Option<u8> {
+        self.uart_index
+    }
+}
+
+/// Prompt of the test terminal
+pub const PROMPT: &[u8] = b"> ";
+
+/// Number of frames drawn by [`draw_frame_count`]
+pub static FRAMES_DRAWN: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+/// Prints its first argument, or nothing when called without arguments
+fn say(terminal: &mut Terminal<FakeIo>, args: &[&str]) {
+    let word = args.first().copied().unwrap_or("");
+    terminal.print_line(word.as_bytes());
+}
+
+/// Returns the commands of the test terminal
+pub fn test_commands() -> &'static [Command<FakeIo>] {
+    &[
+        Command {
+            name: "say",
+            help: "Print a word",
+            args: &[&["hello", "help-me"]],
+            subcommands: &[],
+            handler: say,
+        },
+        Command {
+            name: "status",
+            help: "Unused",
+            args: &[],
+            subcommands: &[],
+            handler: say,
+        },
+        Command {
+            name: "count",
+            help: "Print numbers",
+            args: &[],
+            subcommands: &[],
+            handler: count,
+        },
+        Command {
+            name: "flood",
+            help: "Print numbers all at once",
+            args: &[],
+            subcommands: &[],
+            handler: flood,
+        },
+        Command {
+            name: "login",
+            help: "Ask for a masked password",
+            args: &[],
+            subcommands: &[],
+            handler: login,
+        },
+        Command {
+            name: "key",
+            help: "Ask for a hidden key",
+            args: &[],
+            subcommands: &[],
+            handler: key,
+        },
+        Command {
+            name: "watch",
+            help: "Show a full-screen view",
+            args: &[],
+            subcommands: &[],
+            handler: watch,
+        },
+        Command {
+            name: "pick",
+            help: "Show a form",
+            args: &[],
+            subcommands: &[],
+            handler: pick,
+        },
+    ]
+}
+
+fn pick(terminal: &mut Terminal<FakeIo>, _args: &[&str]) {
+    let form = Form::new("Pick")
+        .field("Colour", Widget::Menu(Menu::new(&["red", "green"], 0)))
+        .field("Count", Widget::Number(NumberField::new(5, 1..=9)));
+    terminal.show_form(form, picked);
+}
+
+/// Prints the values of the submitted form
+fn picked(terminal: &mut Terminal<FakeIo>, form: Option<&Form>) {
+    match form.and_then(|form| Some((form.selected(0)?, form.number(1)?))) {
+        Some((colour, count)) => cli_println!(terminal, "picked {} {}", colour, count),
+        None => cli_println!(terminal, "cancelled"),
+    }
+}
+
+fn watch(terminal: &mut Terminal<FakeIo>, _args: &[&str]) {
+    terminal.show_view(draw_frame_count);
+}
+
+/// Draws the number of frames drawn so far
+fn draw_frame_count(_terminal: &Terminal<FakeIo>, frame: &mut Frame) {
+    let count = FRAMES_DRAWN.fetch_add(1, core::sync::atomic::Ordering::Relaxed) + 1;
+    let _ = fmt::Write::write_fmt(&mut frame.at(1, 2), format_args!("frame {}", count));
+}
+
+/// Prints the numbers from 1 to its argument, one per line
+fn count(terminal: &mut Terminal<FakeIo>, args: &[&str]) {
+    let last = args.first().and_then(|arg| arg.parse().ok()).unwrap_or(0);
+    terminal.produce_output(last, print_number);
+}
+
+/// Prints the number following the index
+fn print_number(terminal: &mut Terminal<FakeIo>, index: usize) {
+    cli_println!(terminal, "{}", index + 1);
+}
+
+/// Prints the numbers from 1 to its argument without waiting for the pager
+fn flood(terminal: &mut Terminal<FakeIo>, args: &[&str]) {
+    let last: u32 = args.first().and_then(|arg| arg.parse().ok()).unwrap_or(0);
+    for number in 1..=last {
+        cli_println!(terminal, "{}", number);
+    }
+}
+
+fn login(terminal: &mut Terminal<FakeIo>, _args: &[&str]) {
+    terminal.request_line(b"Password: ", Echo::Masked, receive);
+}
+
+fn key(terminal: &mut Terminal<FakeIo>, _args: &[&str]) {
+    terminal.request_line(b"Key: ", Echo::Hidden, receive);
+}
+
+/// Prints the requested line it receives
+fn receive(terminal: &mut Terminal<FakeIo>, line: Option<&str>) {
+    match line {
+        Some(line) => cli_println!(terminal, "got {}", line),
+        None => cli_println!(terminal, "cancelled"),
+    }
+}
+
+/// Creates a terminal with the startup output already discarded
+pub fn terminal() -> Terminal<FakeIo> {
+    let mut terminal = Terminal::new(
+        FakeIo::default(),
+        Style::new().foreground(Color::Blue),
+        b"",
+        PROMPT,
+        test_commands(),
+    );
+    terminal.io.take_output();
+    terminal
+}
+
+/// Feeds keystrokes to the terminal and returns the bytes it wrote in response
+pub fn type_keys(terminal: &mut Terminal<FakeIo>, keys: &[u8]) -> Vec<u8> {
+    terminal.process_bytes(keys);
+    terminal.io.take_output()
+}
+
+/// Returns the prompt of the test terminal as printed, in blue
+pub fn prompt() -> Vec<u8> {
+    [b"\x1b[34m", PROMPT, b"\x1b[0m"].concat()
+}
+
+/// Writes a fixed status bar text
+pub fn draw_status(_terminal: &Terminal<FakeIo>, text: &mut StatusText) {
+    let _ = fmt::Write::write_str(text, "ok");
+}
@@ -0,0 +1,164 @@
+//! Command history module for the terminal line editor.
+//!
+//! Stores previously submitted lines in a fixed-capacity ring so they can be
+//! recalled with the up/down arrow keys or searched with Ctrl-R. Once the ring
+//! is full the oldest entry is discarded.
+
+use crate::constants::{HISTORY_CAPACITY, MAX_LINE_LENGTH};
+use heapless::Deque;
+
+/// A single line of terminal input
+pub type Line = heapless::Vec<u8, MAX_LINE_LENGTH>;
+
+/// Overwrites the whole buffer of a line with zeros and empties it
+///
+/// Clearing a line only resets its length and leaves the text in memory, so lines
+/// holding secrets are wiped with this instead.
+///
+/// # Arguments
+///
+/// * `line` - Line to wipe
+pub fn wipe(line: &mut Line) {
+    let _ = line.resize(line.capacity(), 0);
+    for byte in line.iter_mut() {
+        // SAFETY: the byte is a valid, exclusively borrowed `u8`; the volatile write
+        // keeps the compiler from dropping a store that is never read back
+        unsafe { core::ptr::write_volatile(byte, 0) };
+    }
+    line.clear();
+}
+
+/// Fixed-capacity ring of submitted lines with a navigation cursor
+pub struct History {
+    /// Stored lines, oldest at the front and most recent at the back
+    entries: Deque<Line, HISTORY_CAPACITY>,
+
+    /// Number of steps back from the most recent entry, or `None` while editing a new line
+    position: Option<usize>,
+
+    /// Line that was being edited before history navigation started
+    draft: Line,
+}
+
+impl History {
+    /// Creates an empty history
+    pub fn new() -> Self {
+        History {
+            entries: Deque::new(),
+            position: None,
+            draft: Line::new(),
+        }
+    }
+
+    /// Stores a submitted line and resets navigation
+    ///
+    /// Empty lines and repeats of the most recent entry are not stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The submitted line
+    pub fn push(&mut self, line: &[u8]) {
+        self.reset_navigation();
+
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return;
+        }
+
+        if self
+            .entries
+            .back()
+            .is_some_and(|last| last.as_slice() == line)
+        {
+            return;
+        }
+
+        if self.entries.is_full() {
+            self.entries.pop_front();
+        }
+
+        let mut entry = Line::new();
+        let _ = entry.extend_from_slice(line);
+        let _ = self.entries.push_back(entry);
+    }
+
+    /// Steps back to the next older entry
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The line currently being edited, saved as the draft when navigation starts
+    ///
+    /// # Returns
+    ///
+    /// The recalled line, or `None` if there is no older entry
+    pub fn previous(&mut self, current: &[u8]) -> Option<&[u8]> {
+        let next_position = match self.position {
+            None => 0,
+            Some(position) => position + 1,
+        };
+
+        if next_position >= self.entries.len() {
+            return None;
+        }
+
+        if self.position.is_none() {
+            self.draft.clear();
+            let _ = self.draft.extend_from_slice(current);
+        }
+
+        self.position = Some(next_position);
+        self.entry(next_position)
+    }
+
+    /// Steps forward to the next newer entry
+    ///
+    /// Stepping past the most recent entry restores the draft line.
+    ///
+    /// # Returns
+    ///
+    /// The recalled line, or `None` if history navigation is not active
+    pub fn next(&mut self) -> Option<&[u8]> {
+        match self.position? {
+            0 => {
+                self.position = None;
+                Some(self.draft.as_slice())
+            }
+            position => {
+                self.position = Some(position - 1);
+                self.entry(position - 1)
+            }
+        }
+    }
+
+    /// Leaves history navigation without changing the stored entries
+    pub fn reset_navigation(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+
+    /// Finds the most recent entry containing `query`, skipping the newest `from` entries
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Text to look for; an empty query matches nothing
+    /// * `from` - Number of steps back from the most recent entry to start searching at
+    ///
+    /// # Returns
+    ///
+    /// The number of steps back of the matching entry, or `None` if no older entry matches
+    pub fn search(&self, query: &[u8], from: usize) -> Option<usize> {
+        if query.is_empty() {
+            return None;
+        }
+
+        (from..self.entries.len()).find(|&steps_back| {
+            self.entry(steps_back)
+                .is_some_and(|line| line.windows(query.len()).any(|window| window == query))
+        })
+    }
+
+    /// Returns the entry `steps_back` positions before the most recent one
+    pub fn entry(&self, steps_back: usize) -> Option<&[u8]> {
+        let index = self.entries.len().checked_sub(steps_back + 1)?;
+        self.entries.iter().nth(index).map(|line| line.as_slice())
+    }
+}
@@ -0,0 +1,83 @@
+//! Kill ring module for the terminal line editor.
+//!
+//! Text removed with Ctrl-K, Ctrl-U or Ctrl-W is kept in a small ring so it can
+//! be yanked back with Ctrl-Y, and Alt-Y can then cycle through older kills.
+//! Once the ring is full the oldest entry is discarded.
+
+use crate::constants::KILL_RING_CAPACITY;
+use crate::peripherals::uart::history::Line;
+use heapless::Deque;
+
+/// Fixed-capacity ring of killed text
+pub struct KillRing {
+    /// Killed text, oldest at the front and most recent at the back
+    entries: Deque<Line, KILL_RING_CAPACITY>,
+}
+
+impl KillRing {
+    /// Creates an empty kill ring
+    pub fn new() -> Self {
+        KillRing {
+            entries: Deque::new(),
+        }
+    }
+
+    /// Stores killed text as the most recent entry
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The removed text; empty text is not stored
+    pub fn push(&mut self, text: &[u8]) {
+        if text.is_empty() {
+            return;
+        }
+
+        if self.entries.is_full() {
+            self.entries.pop_front();
+        }
+        let _ = self
+            .entries
+            .push_back(Line::from_slice(text).unwrap_or_default());
+    }
+
+    /// Returns the entry `steps_back` positions before the most recent one
+    ///
+    /// The index wraps around, so Alt-Y cycles through the whole ring.
+    pub fn get(&self, steps_back: usize) -> Option<&[u8]> {
+        let len = self.entries.len();
+        if len == 0 {
+            return None;
+        }
+
+        let index = len - 1 - steps_back % len;
+        self.entries.iter().nth(index).map(|line| line.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_recent_kill_comes_first_and_index_wraps() {
+        let mut ring = KillRing::new();
+        ring.push(b"one");
+        ring.push(b"");
+        ring.push(b"two");
+
+        assert_eq!(ring.get(0), Some(b"two".as_slice()));
+        assert_eq!(ring.get(1), Some(b"one".as_slice()));
+        assert_eq!(ring.get(2), Some(b"two".as_slice()));
+    }
+
+    #[test]
+    fn oldest_kill_is_dropped_when_full() {
+        let mut ring = KillRing::new();
+        for text in [b"a", b"b", b"c", b"d", b"e"] {
+            ring.push(text);
+        }
+
+        assert_eq!(ring.get(0), Some(b"e".as_slice()));
+        assert_eq!(ring.get(KILL_RING_CAPACITY - 1), Some(b"b".as_slice()));
+    }
+}
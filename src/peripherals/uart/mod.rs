@@ -4,33 +4,238 @@
 //! with interrupt-driven input handling
 use rp2040_pac::{RESETS, UART0, interrupt};
 
-use crate::constants::MAX_LINE_LENGTH;
-use core::cell::RefCell;
-use cortex_m::interrupt::{Mutex, free};
+use crate::constants::{LOG_QUEUE_SIZE, RX_BUFFER_SIZE};
+use core::cell::{Cell, RefCell};
+use cortex_m::interrupt::{CriticalSection, Mutex, free};
 use heapless::spsc::Queue;
 
+pub mod escape;
+#[cfg(test)]
+pub mod fake;
+pub mod history;
+pub mod kill_ring;
+pub mod pager;
+pub mod print;
+pub mod prompt;
+pub mod screen;
+pub mod status_bar;
+pub mod style;
 pub mod terminal;
+pub mod undo;
+pub mod utf8;
+pub mod vi;
+pub mod widgets;
 
-/// Default UART baud rate
-const UART_BAUD_RATE: u32 = 115200;
+/// Baud rate the UART starts with
+const DEFAULT_BAUD_RATE: u32 = 115200;
 
 /// Global static queue for storing UART input received from ISR
 /// Uses a mutex-protected RefCell for safe concurrent access
-static INPUT_QUEUE: Mutex<RefCell<Queue<u8, MAX_LINE_LENGTH>>> =
+static INPUT_QUEUE: Mutex<RefCell<Queue<u8, RX_BUFFER_SIZE>>> =
     Mutex::new(RefCell::new(Queue::new()));
 
+/// Number of received bytes lost since the terminal last checked, either because the
+/// input queue was full or because the hardware FIFO overran
+static DROPPED_INPUT: Mutex<Cell<usize>> = Mutex::new(Cell::new(0));
+
+/// Receive errors counted since start-up, see [`UartErrors`]
+static UART_ERRORS: Mutex<Cell<UartErrors>> = Mutex::new(Cell::new(UartErrors::new()));
+
+/// Global static queue for log messages waiting to be printed above the prompt
+///
+/// Messages are stored back to back, each terminated by a line feed.
+static LOG_QUEUE: Mutex<RefCell<Queue<u8, LOG_QUEUE_SIZE>>> =
+    Mutex::new(RefCell::new(Queue::new()));
+
+/// Queues a message to be printed above the prompt on the next terminal update
+///
+/// Safe to call from interrupt handlers and other code that does not own the CLI.
+/// Messages are kept whole: one that does not fit in the remaining queue space is dropped.
+///
+/// # Arguments
+///
+/// * `message` - Text to print; line feeds split it over several lines
+///
+/// # Returns
+///
+/// `true` if the message was queued
+pub fn log(message: &[u8]) -> bool {
+    // Enter interrupt-free section
+    free(|cs| queue_log(cs, message))
+}
+
+/// Appends a message and its line feed to [`LOG_QUEUE`], unless they do not fit whole
+///
+/// # Arguments
+///
+/// * `cs` - Token of the interrupt-free section the queue is accessed in
+/// * `message` - Text to queue
+///
+/// # Returns
+///
+/// `true` if the message was queued
+fn queue_log(cs: &CriticalSection, message: &[u8]) -> bool {
+    let mut queue = LOG_QUEUE.borrow(cs).borrow_mut();
+    if queue.capacity() - queue.len() <= message.len() {
+        return false;
+    }
+
+    for &byte in message.iter().chain(b"\n") {
+        let _ = queue.enqueue(byte);
+    }
+    true
+}
+
+/// Removes all messages from [`LOG_QUEUE`]
+///
+/// # Arguments
+///
+/// * `cs` - Token of the interrupt-free section the queue is accessed in
+///
+/// # Returns
+///
+/// The queued messages, each terminated by a line feed
+fn take_log(cs: &CriticalSection) -> heapless::Vec<u8, LOG_QUEUE_SIZE> {
+    let mut buffer = heapless::Vec::new();
+    let mut queue = LOG_QUEUE.borrow(cs).borrow_mut();
+    while let Some(byte) = queue.dequeue() {
+        let _ = buffer.push(byte);
+    }
+    buffer
+}
+
+/// Counts of receive errors seen since start-up
+///
+/// Counters wrap around rather than saturate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UartErrors {
+    /// Bytes lost because the hardware FIFO was full
+    pub overrun: u32,
+
+    /// Break conditions, where the line was held low for longer than a frame
+    pub breaks: u32,
+
+    /// Bytes received with a wrong parity bit
+    pub parity: u32,
+
+    /// Bytes received without a valid stop bit
+    pub framing: u32,
+
+    /// Bytes lost because the input queue was full
+    pub queue_full: u32,
+}
+
+impl UartErrors {
+    /// Creates a set of counters that are all zero
+    pub const fn new() -> Self {
+        UartErrors {
+            overrun: 0,
+            breaks: 0,
+            parity: 0,
+            framing: 0,
+            queue_full: 0,
+        }
+    }
+}
+
 /// Represents word length configurations for UART communication
-#[allow(unused)]
-enum UartWordLength {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UartWordLength {
     Five = 0b00,
     Six = 0b01,
     Seven = 0b10,
     Eight = 0b11,
 }
 
+/// Represents parity bit configurations for UART communication
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UartParity {
+    /// No parity bit is sent
+    None,
+
+    /// The parity bit makes the number of set bits even
+    Even,
+
+    /// The parity bit makes the number of set bits odd
+    Odd,
+}
+
+/// Speed and frame format of a UART, always with one stop bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UartSettings {
+    /// Bits sent per second
+    pub baud_rate: u32,
+
+    /// Number of data bits in each frame
+    pub word_length: UartWordLength,
+
+    /// Parity bit sent after the data bits
+    pub parity: UartParity,
+}
+
+impl UartSettings {
+    /// Creates the settings the UART starts with: 115200 baud, 8 data bits, no parity
+    pub const fn new() -> Self {
+        UartSettings {
+            baud_rate: DEFAULT_BAUD_RATE,
+            word_length: UartWordLength::Eight,
+            parity: UartParity::None,
+        }
+    }
+}
+
+/// Computes the baud rate divisor for a peripheral clock frequency
+///
+/// From section 4.2.3.2.1 of rp2040-datasheet:
+/// The baud rate divisor is a 22-bit number consisting of a 16-bit integer and
+/// a 6-bit fractional part.
+///
+/// UARTCLK / (16 * BaudRate) = BRD(integer) + BRD(fractional)
+///
+/// You can calculate the 6-bit number by taking the fractional part of the
+/// required baud rate divisor and multiplying it by 64 (that is, 2^n,
+/// where n is the width of the UARTFBRD Register) and adding
+/// 0.5 to account for rounding errors
+/// m = integer(BRD(fractional) * 2^n + 0.5), n=6
+///
+/// # Arguments
+///
+/// * `peripheral_clock_freq` - The peripheral clock frequency in Hz
+/// * `baud_rate` - Bits sent per second
+///
+/// # Returns
+///
+/// The integer and fractional parts of the divisor, or `None` if the integer part does
+/// not fit in 16 bits or is zero, so the clock cannot generate the baud rate
+fn baud_rate_divisor(peripheral_clock_freq: u32, baud_rate: u32) -> Option<(u32, u32)> {
+    if baud_rate == 0 {
+        return None;
+    }
+    let divisor = peripheral_clock_freq as f32 / (16f32 * baud_rate as f32);
+    let integer = divisor as u32;
+
+    // Calculate the 6-bit register value representing the fractional part
+    let uart_rounding_factor = 0.5f32;
+    let fraction = divisor - integer as f32;
+    let fraction_bits = ((fraction * 64f32) + uart_rounding_factor) as u32;
+
+    // A fraction rounded up to a whole carries into the integer part
+    let (integer, fraction_bits) = if fraction_bits == 64 {
+        (integer + 1, 0)
+    } else {
+        (integer, fraction_bits)
+    };
+    (1..=u32::from(u16::MAX))
+        .contains(&integer)
+        .then_some((integer, fraction_bits))
+}
+
 /// UART0 interrupt handler
 ///
-/// Processes received characters and stores them in the input queue.
+/// Processes received characters and stores them in the input queue. Bytes that cannot
+/// be stored, and overruns flagged by the hardware, are counted in `DROPPED_INPUT`; all
+/// receive errors are also counted in `UART_ERRORS`. Break conditions are logged above
+/// the prompt, as they usually mean the remote end was reset or unplugged.
 #[interrupt]
 fn UART0_IRQ() {
     let uart = unsafe { &*UART0::ptr() };
@@ -41,14 +246,34 @@ fn UART0_IRQ() {
     if rx_interrupt_set || rx_timeout_interrupt_set {
         let mut is_rx_fifo_empty = uart.uartfr().read().rxfe().bit_is_set();
         while !is_rx_fifo_empty {
-            let data = uart.uartdr().read().data().bits();
+            let entry = uart.uartdr().read();
+            let data = entry.data().bits();
+            let overrun = entry.oe().bit_is_set();
 
             // Enter interrupt-free section
             free(|cs| {
                 let mut queue = INPUT_QUEUE.borrow(cs).borrow_mut();
-                let _ = queue.enqueue(data);
+                let queue_full = queue.enqueue(data).is_err();
+                let lost = usize::from(queue_full) + usize::from(overrun);
+                if lost > 0 {
+                    let dropped = DROPPED_INPUT.borrow(cs);
+                    dropped.set(dropped.get() + lost);
+                }
+
+                let errors = UART_ERRORS.borrow(cs);
+                let mut counts = errors.get();
+                counts.overrun = counts.overrun.wrapping_add(u32::from(overrun));
+                counts.breaks = counts.breaks.wrapping_add(u32::from(entry.be().bit()));
+                counts.parity = counts.parity.wrapping_add(u32::from(entry.pe().bit()));
+                counts.framing = counts.framing.wrapping_add(u32::from(entry.fe().bit()));
+                counts.queue_full = counts.queue_full.wrapping_add(u32::from(queue_full));
+                errors.set(counts);
             });
 
+            if entry.be().bit_is_set() {
+                log(b"uart: break received");
+            }
+
             is_rx_fifo_empty = uart.uartfr().read().rxfe().bit_is_set();
         }
     }
@@ -56,30 +281,98 @@ fn UART0_IRQ() {
     uart.uarticr().write(|w| unsafe { w.bits(0xFFFF) });
 }
 
-/// Trait defining the interface for serial port operations
-trait SerialPort {
-    /// Sends a single byte through the serial port
+/// Byte-oriented transport the terminal reads keystrokes from and writes output to
+///
+/// Implemented by [`Uart`] on the target; host tests provide an in-memory implementation.
+/// Transports own their underlying peripheral, so they are required to be `'static`.
+pub trait ByteIo: 'static {
+    /// Sends a single byte
     fn putc(&mut self, c: u8);
 
-    /// Prints a byte slice to the serial port
-    fn print(&mut self, s: &[u8]);
+    /// Sends a byte slice
+    fn print(&mut self, s: &[u8]) {
+        for &byte in s {
+            self.putc(byte);
+        }
+    }
+
+    /// Retrieves all bytes received since the previous call
+    fn get_input(&mut self) -> heapless::Vec<u8, RX_BUFFER_SIZE>;
 
+    /// Returns the number of received bytes lost since the previous call and resets the count
+    ///
+    /// Transports that cannot lose input keep the default, which reports none.
+    fn take_dropped_input(&mut self) -> usize {
+        0
+    }
+
+    /// Retrieves the log messages queued since the previous call, each terminated by a
+    /// line feed
+    ///
+    /// Transports without a log source keep the default, which returns nothing.
+    fn get_log(&mut self) -> heapless::Vec<u8, LOG_QUEUE_SIZE> {
+        heapless::Vec::new()
+    }
+
+    /// Returns the index of the UART this transport runs on
+    ///
+    /// Transports that are not a UART keep the default, which returns `None`.
+    fn uart_index(&self) -> Option<u8> {
+        None
+    }
+
+    /// Returns the receive errors counted since start-up
+    ///
+    /// Transports that do not count errors keep the default, which returns `None`.
+    fn uart_errors(&self) -> Option<UartErrors> {
+        None
+    }
+
+    /// Returns the speed and frame format of the UART this transport runs on
+    ///
+    /// Transports that are not a UART keep the default, which returns `None`.
+    fn uart_settings(&self) -> Option<UartSettings> {
+        None
+    }
+
+    /// Changes the speed and frame format of the UART this transport runs on
+    ///
+    /// Output already sent is transmitted with the previous settings first. Transports
+    /// that are not a UART keep the default, which changes nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - Settings to switch to
+    ///
+    /// # Returns
+    ///
+    /// `true` if the settings were applied, `false` if the transport is not a UART or
+    /// cannot generate the baud rate
+    fn set_uart_settings(&mut self, _settings: UartSettings) -> bool {
+        false
+    }
+}
+
+/// Trait defining the interface for serial port operations
+#[cfg_attr(feature = "sim", allow(dead_code))]
+trait SerialPort {
     /// Enables or disables the FIFO buffer
     fn set_fifo_enable(&mut self, enable: bool);
 
     /// Configures the number of stop bits (one or two)
     fn use_two_stop_bits(&mut self, use_two_stop_bits: bool);
 
-    /// Enables or disables parity bit
-    fn set_parity(&mut self, parity: bool);
+    /// Enables or disables the parity bit and selects even or odd parity
+    fn set_parity(&mut self, parity: UartParity);
 
-    /// Configures the baud rate
+    /// Configures the baud rate of the current settings
     fn config_baud_rate(&mut self);
 
     /// Sets the word length for UART communication
     fn config_word_length(&mut self, word_length: UartWordLength);
 
-    /// Configures UART parameters (word length, FIFO, stop bits, parity)
+    /// Configures UART parameters (word length, FIFO, stop bits, parity) of the current
+    /// settings
     fn config_parameters(&mut self);
 
     /// Configures UART interrupts
@@ -87,19 +380,22 @@ trait SerialPort {
 
     /// Enables or disables the UART peripheral
     fn set_peripheral_enable(&mut self, enable: bool);
-
-    /// Retrieves input from the UART buffer
-    fn get_input(&mut self) -> heapless::Vec<u8, MAX_LINE_LENGTH>;
 }
+// The simulator talks to its console instead of the UART
+#[cfg_attr(feature = "sim", allow(dead_code))]
 pub struct Uart {
     /// The UART0 peripheral instance
     uart_peripheral: UART0,
 
     /// The peripheral clock frequency in Hz
     peripheral_clock_freq: u32,
+
+    /// Speed and frame format the peripheral is configured for
+    settings: UartSettings,
 }
 
 /// UART peripheral wrapper struct
+#[cfg_attr(feature = "sim", allow(dead_code))]
 impl Uart {
     /// Creates a new UART instance with the specified parameters
     ///
@@ -111,6 +407,7 @@ impl Uart {
         let mut uart = Uart {
             uart_peripheral,
             peripheral_clock_freq,
+            settings: UartSettings::new(),
         };
 
         uart.reset_peripheral(resets);
@@ -134,41 +431,18 @@ impl Uart {
 }
 impl SerialPort for Uart {
     fn config_baud_rate(&mut self) {
-        /*
-           From section 4.2.3.2.1 of rp2040-datasheet:
-           The baud rate divisor is a 22-bit number consisting of a 16-bit integer and
-           a 6-bit fractional part.
-
-           UARTCLK / (16 * BaudRate) = BRD(integer) + BRD(fractional)
-
-           You can calculate the 6-bit number by taking the fractional part of the
-           required baud rate divisor and multiplying it by 64 (that is, 2^n,
-           where n is the width of the UARTFBRD Register) and adding
-           0.5 to account for rounding errors
-           m = integer(BRD(fractional) * 2^n + 0.5), n=6
-        */
-
-        // Set integer part
-        let baud_rate_divisor_integer =
-            (self.peripheral_clock_freq as f32 / (16f32 * UART_BAUD_RATE as f32)) as u32;
+        let Some((baud_rate_divisor_integer, fraction_bits)) =
+            baud_rate_divisor(self.peripheral_clock_freq, self.settings.baud_rate)
+        else {
+            return;
+        };
 
         // Integer part
         self.uart_peripheral
             .uartibrd()
             .write(|w| unsafe { w.bits(baud_rate_divisor_integer) });
 
-        // Calculate fractional part (round to nearest)
-        let peripheral_clock_freq_float = self.peripheral_clock_freq as f32;
-        let uart_baud_rate_float = UART_BAUD_RATE as f32;
-
-        // Calculate fractional part of the divisor
-        let fraction = (peripheral_clock_freq_float / (16f32 * uart_baud_rate_float))
-            - (baud_rate_divisor_integer as f32);
-
-        let uart_rounding_factor = 0.5f32;
-
-        // Calculate the 6-bit register value representing the fractional part
-        let fraction_bits = ((fraction * 64f32) + uart_rounding_factor) as u32;
+        // Fractional part
         self.uart_peripheral
             .uartfbrd()
             .write(|w| unsafe { w.bits(fraction_bits) });
@@ -204,15 +478,23 @@ impl SerialPort for Uart {
         }
     }
 
-    fn set_parity(&mut self, parity: bool) {
-        if parity {
-            self.uart_peripheral
-                .uartlcr_h()
-                .modify(|_, w| w.pen().set_bit());
-        } else {
-            self.uart_peripheral
-                .uartlcr_h()
-                .modify(|_, w| w.pen().clear_bit());
+    fn set_parity(&mut self, parity: UartParity) {
+        match parity {
+            UartParity::None => {
+                self.uart_peripheral
+                    .uartlcr_h()
+                    .modify(|_, w| w.pen().clear_bit());
+            }
+            UartParity::Even => {
+                self.uart_peripheral
+                    .uartlcr_h()
+                    .modify(|_, w| w.pen().set_bit().eps().set_bit());
+            }
+            UartParity::Odd => {
+                self.uart_peripheral
+                    .uartlcr_h()
+                    .modify(|_, w| w.pen().set_bit().eps().clear_bit());
+            }
         }
     }
 
@@ -223,23 +505,10 @@ impl SerialPort for Uart {
     }
 
     fn config_parameters(&mut self) {
-        self.config_word_length(UartWordLength::Eight);
+        self.config_word_length(self.settings.word_length);
         self.set_fifo_enable(true);
         self.use_two_stop_bits(false);
-        self.set_parity(false);
-    }
-
-    fn get_input(&mut self) -> heapless::Vec<u8, MAX_LINE_LENGTH> {
-        let mut buffer = heapless::Vec::new();
-
-        // Enter interrupt-free section
-        free(|cs| {
-            let mut queue = INPUT_QUEUE.borrow(cs).borrow_mut();
-            while let Some(byte) = queue.dequeue() {
-                let _ = buffer.push(byte);
-            }
-        });
-        buffer
+        self.set_parity(self.settings.parity);
     }
 
     fn config_interrupts(&mut self) {
@@ -284,7 +553,9 @@ impl SerialPort for Uart {
             }
         }
     }
+}
 
+impl ByteIo for Uart {
     fn putc(&mut self, c: u8) {
         // Wait until TX FIFO is not full
         while self.uart_peripheral.uartfr().read().txff().bit_is_set() {}
@@ -294,9 +565,94 @@ impl SerialPort for Uart {
             .write(|w| unsafe { w.data().bits(c) });
     }
 
-    fn print(&mut self, s: &[u8]) {
-        for &byte in s {
-            self.putc(byte);
+    fn get_input(&mut self) -> heapless::Vec<u8, RX_BUFFER_SIZE> {
+        let mut buffer = heapless::Vec::new();
+
+        // Enter interrupt-free section
+        free(|cs| {
+            let mut queue = INPUT_QUEUE.borrow(cs).borrow_mut();
+            while let Some(byte) = queue.dequeue() {
+                let _ = buffer.push(byte);
+            }
+        });
+        buffer
+    }
+
+    fn take_dropped_input(&mut self) -> usize {
+        // Enter interrupt-free section
+        free(|cs| DROPPED_INPUT.borrow(cs).replace(0))
+    }
+
+    fn get_log(&mut self) -> heapless::Vec<u8, LOG_QUEUE_SIZE> {
+        // Enter interrupt-free section
+        free(take_log)
+    }
+
+    fn uart_index(&self) -> Option<u8> {
+        Some(0)
+    }
+
+    fn uart_errors(&self) -> Option<UartErrors> {
+        // Enter interrupt-free section
+        Some(free(|cs| UART_ERRORS.borrow(cs).get()))
+    }
+
+    fn uart_settings(&self) -> Option<UartSettings> {
+        Some(self.settings)
+    }
+
+    fn set_uart_settings(&mut self, settings: UartSettings) -> bool {
+        if baud_rate_divisor(self.peripheral_clock_freq, settings.baud_rate).is_none() {
+            return false;
         }
+
+        // Wait until the transmitter is idle so output already sent is not garbled
+        while self.uart_peripheral.uartfr().read().busy().bit_is_set() {}
+
+        self.settings = settings;
+        self.set_peripheral_enable(false);
+        self.config_baud_rate();
+        self.config_parameters();
+        self.set_peripheral_enable(true);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_messages_are_queued_whole_until_taken() {
+        // `free` only links on the target; host tests have no interrupts to mask, and no
+        // other test uses the log queue
+        let cs = unsafe { CriticalSection::new() };
+        let message = [b'x'; 100];
+
+        assert!(queue_log(&cs, &message));
+        assert!(queue_log(&cs, &message));
+        // 53 bytes are left, one short of the message and its line feed
+        assert!(!queue_log(&cs, &message[..53]));
+        assert!(queue_log(&cs, &message[..52]));
+        assert!(!queue_log(&cs, b""));
+
+        let expected = [&message[..], b"\n", &message, b"\n", &message[..52], b"\n"].concat();
+        assert_eq!(take_log(&cs), expected.as_slice());
+        assert!(take_log(&cs).is_empty());
+        assert!(queue_log(&cs, b"after"));
+        assert_eq!(take_log(&cs), b"after\n".as_slice());
+    }
+
+    #[test]
+    fn baud_rate_divisor_is_rounded_and_range_checked() {
+        let clock = 125_000_000;
+        assert_eq!(baud_rate_divisor(clock, 115_200), Some((67, 52)));
+        assert_eq!(baud_rate_divisor(clock, 9600), Some((813, 51)));
+        assert_eq!(baud_rate_divisor(clock, 7_812_500), Some((1, 0)));
+
+        // Too fast, too slow, or no rate at all
+        assert_eq!(baud_rate_divisor(clock, 8_000_000), None);
+        assert_eq!(baud_rate_divisor(clock, 100), None);
+        assert_eq!(baud_rate_divisor(clock, 0), None);
     }
 }
@@ -0,0 +1,436 @@
+//! Pager module for long command output.
+//!
+//! Counts the rows written by a command against the terminal height, so that output
+//! can be paused before the first row of a page scrolls out of view. The last row of
+//! the screen is kept free for the `--More--` prompt shown while paused.
+//!
+//! The terminal methods that hold output back while paused, read the pager keys and
+//! produce long output one part at a time live here too.
+
+use crate::peripherals::uart::ByteIo;
+use crate::peripherals::uart::style::Style;
+use crate::peripherals::uart::terminal::{ASCIICode, ASCIIControl, Terminal};
+use crate::peripherals::uart::utf8::{char_width, is_boundary, next_boundary};
+
+/// Row and column tracking for the output of one command
+pub struct Pager {
+    /// Number of rows of output shown per page
+    page_rows: usize,
+
+    /// Number of columns of the terminal window
+    width: usize,
+
+    /// Row of the page the output has reached
+    row: usize,
+
+    /// Column the output has reached
+    column: usize,
+
+    /// Whether the user quit, so the remaining output is discarded
+    aborted: bool,
+}
+
+impl Pager {
+    /// Creates a pager for output starting at the beginning of a row
+    ///
+    /// # Arguments
+    ///
+    /// * `height` - Number of rows of the terminal window
+    /// * `width` - Number of columns of the terminal window
+    pub fn new(height: usize, width: usize) -> Self {
+        Pager {
+            page_rows: height.saturating_sub(1).max(1),
+            width: width.max(1),
+            row: 0,
+            column: 0,
+            aborted: false,
+        }
+    }
+
+    /// Returns how much of the text fits on the current page and counts it as written
+    ///
+    /// Output stops before a line feed or character that would start a row past the
+    /// page, including a character that wraps onto one.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text about to be written; line feeds and carriage returns move the
+    ///   output position, other control characters take no space
+    ///
+    /// # Returns
+    ///
+    /// The number of leading bytes that can be written before pausing
+    pub fn fit(&mut self, text: &[u8]) -> usize {
+        for (index, &byte) in text.iter().enumerate() {
+            if !is_boundary(text, index) {
+                continue;
+            }
+
+            match byte {
+                b'\r' => self.column = 0,
+                b'\n' => {
+                    if self.row >= self.page_rows {
+                        return index;
+                    }
+                    self.row += 1;
+                    self.column = 0;
+                }
+                _ => {
+                    let cells = cell_count(text, index);
+                    if self.row >= self.page_rows {
+                        return index;
+                    }
+                    if self.column + cells > self.width {
+                        if self.row + 1 >= self.page_rows {
+                            return index;
+                        }
+                        self.row += 1;
+                        self.column = 0;
+                    }
+                    self.column += cells;
+                }
+            }
+        }
+        text.len()
+    }
+
+    /// Returns whether output stopped partway through a row
+    ///
+    /// The `--More--` prompt must then be shown at the start of the next row.
+    pub fn is_mid_row(&self) -> bool {
+        self.column > 0
+    }
+
+    /// Continues with a full page, starting on the row the prompt was shown on
+    pub fn next_page(&mut self) {
+        self.row = 0;
+        self.column = 0;
+    }
+
+    /// Continues with a single row, starting on the row the prompt was shown on
+    pub fn next_line(&mut self) {
+        self.row = self.page_rows - 1;
+        self.column = 0;
+    }
+
+    /// Discards the remaining output
+    pub fn abort(&mut self) {
+        self.aborted = true;
+    }
+
+    /// Returns whether the user quit and the remaining output is discarded
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+}
+
+/// Returns the number of cells the character starting at `index` occupies
+fn cell_count(text: &[u8], index: usize) -> usize {
+    if text[index].is_ascii_control() {
+        return 0;
+    }
+    core::str::from_utf8(&text[index..next_boundary(text, index)])
+        .ok()
+        .and_then(|character| character.chars().next())
+        .map_or(1, char_width)
+}
+
+/// Prompt shown while paged output waits for a key
+const MORE_PROMPT: &[u8] = b"--More--";
+
+/// Signature of a function writing one part of long command output, registered with
+/// [`Terminal::produce_output`]
+///
+/// The function receives the terminal and the index of the part to write.
+pub type OutputStep<IO> = fn(terminal: &mut Terminal<IO>, index: usize);
+
+impl<IO: ByteIo> Terminal<IO> {
+    /// Writes text, pausing whenever a page is full while command output is paged
+    ///
+    /// Pausing does not wait for a key: the output that follows is held back and shown
+    /// by [`Self::process_input`] once the user goes on. Only output that does not fit
+    /// in what is held back waits for the key in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text to write
+    pub(super) fn output(&mut self, text: &[u8]) {
+        let Some(pager) = &mut self.pager else {
+            self.io.print(text);
+            return;
+        };
+        if pager.is_aborted() {
+            return;
+        }
+        if self.output_paused {
+            self.hold_output(text);
+            return;
+        }
+
+        let fits = pager.fit(text);
+        self.io.print(&text[..fits]);
+        if fits < text.len() {
+            self.pause_output();
+            self.hold_output(&text[fits..]);
+        }
+    }
+
+    /// Keeps command output until paged output goes on
+    ///
+    /// Text that does not fit in [`PAGED_OUTPUT_SIZE`](crate::constants::PAGED_OUTPUT_SIZE) bytes is never dropped: the
+    /// command then waits here until the user goes on or quits. Commands writing long
+    /// output avoid this by producing it with [`Self::produce_output`].
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text to show later
+    fn hold_output(&mut self, text: &[u8]) {
+        while self.paged_output.capacity() - self.paged_output.len() < text.len() {
+            self.process_paged_input();
+            self.refresh_status_bar();
+            if !self.output_paused {
+                self.output(text);
+                return;
+            }
+        }
+        let _ = self.paged_output.extend_from_slice(text);
+    }
+
+    /// Shows the `--More--` prompt, leaving the running command to finish while the
+    /// output waits for a key
+    fn pause_output(&mut self) {
+        if self.pager.as_ref().is_some_and(Pager::is_mid_row) {
+            let newline = self.output_newline.as_bytes();
+            self.io.print(newline);
+        }
+        self.apply_style(Style::new().reverse());
+        self.io.print(MORE_PROMPT);
+        self.clear_formatting();
+        self.output_paused = true;
+    }
+
+    /// Applies keys typed at the `--More--` prompt
+    ///
+    /// Space shows the next page and Enter the next row; `q` or Ctrl-C discards the rest
+    /// of the output. Other keys are ignored. Once the output goes on, the keys received
+    /// after the last one used are kept for later.
+    pub(super) fn process_paged_input(&mut self) {
+        let mut input = core::mem::take(&mut self.held_input);
+        if input.is_empty() {
+            input = self.io.get_input();
+        }
+
+        for (index, &key) in input.iter().enumerate() {
+            if !self.output_paused {
+                let _ = self.held_input.extend_from_slice(&input[index..]);
+                return;
+            }
+
+            let line_ending =
+                key == ASCIICode::CarriageReturn as u8 || key == ASCIICode::Newline as u8;
+            let next_line = line_ending && self.accept_line_ending(key);
+            if !line_ending {
+                self.pending_line_ending = None;
+            }
+
+            let Some(pager) = &mut self.pager else {
+                return;
+            };
+            match key {
+                x if x == ASCIICode::Space as u8 => pager.next_page(),
+                _ if next_line => pager.next_line(),
+                b'q' | b'Q' => pager.abort(),
+                x if x == ASCIICode::CtrlC as u8 => pager.abort(),
+                _ => continue,
+            }
+            self.resume_output();
+        }
+    }
+
+    /// Removes the `--More--` prompt and shows the output held back, until the next page
+    /// is full
+    fn resume_output(&mut self) {
+        self.io.putc(ASCIICode::CarriageReturn as u8);
+        if self.plain {
+            for _ in MORE_PROMPT {
+                self.io.putc(ASCIICode::Space as u8);
+            }
+            self.io.putc(ASCIICode::CarriageReturn as u8);
+        } else {
+            self.print_control_sequence(ASCIIControl::ClearToEndOfLine.as_bytes());
+        }
+        self.output_paused = false;
+
+        let held = core::mem::take(&mut self.paged_output);
+        self.output(&held);
+    }
+
+    /// Writes long command output one part at a time
+    ///
+    /// Intended for command handlers whose output may span many pages. The handler
+    /// returns at once; `step` is then called for each index in turn, as long as the
+    /// output is not paused at the `--More--` prompt, so no more output is produced
+    /// than the user has seen. Once the user quits the pager, the remaining parts are
+    /// never written.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Number of parts, `step` is called with the indices `0..count`
+    /// * `step` - Function writing the part with the given index
+    pub fn produce_output(&mut self, count: usize, step: OutputStep<IO>) {
+        self.output_steps = Some((step, 0..count));
+    }
+
+    /// Writes the parts of command output registered with [`Self::produce_output`] until
+    /// a page is full, and ends the output once it is complete
+    pub(super) fn continue_output(&mut self) {
+        while !self.output_paused && !self.output_aborted() {
+            let Some((step, mut indices)) = self.output_steps.take() else {
+                break;
+            };
+            let Some(index) = indices.next() else {
+                break;
+            };
+            self.output_steps = Some((step, indices));
+            step(self, index);
+        }
+        if !self.output_paused {
+            self.finish_output();
+        }
+    }
+
+    /// Ends the output of the command once it is complete, and shows the prompt
+    fn finish_output(&mut self) {
+        self.pager = None;
+        self.paged_output.clear();
+        self.output_steps = None;
+        if self.view.is_none() {
+            self.print_prompt();
+        }
+    }
+
+    /// Returns whether the user quit the pager, so the running command's output is
+    /// discarded
+    fn output_aborted(&self) -> bool {
+        self.pager.as_ref().is_some_and(Pager::is_aborted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::uart::fake::{prompt, terminal, type_keys};
+
+    #[test]
+    fn stops_when_the_page_is_full() {
+        let mut pager = Pager::new(4, 80);
+        assert_eq!(pager.fit(b"one\r\ntwo\r\nthree\r\n"), 17);
+        assert_eq!(pager.fit(b"four\r\n"), 0);
+        assert!(!pager.is_mid_row());
+
+        pager.next_line();
+        assert_eq!(pager.fit(b"four\r\nfive\r\n"), 6);
+
+        pager.next_page();
+        assert_eq!(pager.fit(b"five\r\n\r\n\r\n\r\n"), 11);
+    }
+
+    #[test]
+    fn wrapped_rows_are_counted() {
+        let mut pager = Pager::new(3, 4);
+        assert_eq!(pager.fit(b"abcdefghij"), 8);
+        assert!(pager.is_mid_row());
+
+        // A wide character that does not fit at the end of a row wraps as a whole
+        let mut pager = Pager::new(3, 4);
+        assert_eq!(pager.fit("abc\u{4e2d}\u{4e2d}\u{4e2d}".as_bytes()), 9);
+    }
+
+    #[test]
+    fn aborting_is_remembered() {
+        let mut pager = Pager::new(24, 80);
+        assert!(!pager.is_aborted());
+        pager.abort();
+        assert!(pager.is_aborted());
+    }
+
+    #[test]
+    fn long_output_pauses_at_the_end_of_each_page() {
+        let mut terminal = terminal();
+        terminal.set_height(Some(4));
+        type_keys(&mut terminal, b"count 9");
+        let more = b"\x1b[7m--More--\x1b[0m";
+        let clear = b"\r\x1b[0K";
+        assert_eq!(
+            type_keys(&mut terminal, b"\r"),
+            [b"\r\n1\r\n2\r\n3\r\n".as_slice(), more].concat()
+        );
+
+        // Waiting for a key does not hold up the caller
+        terminal.process_input();
+        assert_eq!(terminal.io.take_output(), b"");
+
+        // Space shows a page, Enter a row and q discards the rest
+        terminal.io.type_bytes(b" \rqsay");
+        terminal.process_input();
+        let expected = [
+            clear.as_slice(),
+            b"4\r\n5\r\n6\r\n",
+            more,
+            clear,
+            b"7\r\n",
+            more,
+            clear,
+            &prompt(),
+        ]
+        .concat();
+        assert_eq!(terminal.io.take_output(), expected);
+
+        // Keys typed after the pager's are processed as usual
+        terminal.process_input();
+        assert_eq!(terminal.io.take_output(), b"say");
+    }
+
+    #[test]
+    fn output_is_produced_no_faster_than_it_is_paged() {
+        let mut terminal = terminal();
+        terminal.set_height(Some(4));
+        type_keys(&mut terminal, b"count 1000000\r");
+        assert_eq!(terminal.output_steps.as_ref().unwrap().1.start, 4);
+
+        // Only the parts needed for the next page are produced, and q drops the rest
+        terminal.io.type_bytes(b" ");
+        terminal.process_input();
+        assert_eq!(terminal.output_steps.as_ref().unwrap().1.start, 7);
+        terminal.io.type_bytes(b"q");
+        terminal.process_input();
+        assert!(terminal.output_steps.is_none());
+        assert!(terminal.io.take_output().ends_with(&prompt()));
+    }
+
+    #[test]
+    fn output_written_at_once_waits_for_the_pager_instead_of_being_dropped() {
+        let mut terminal = terminal();
+        terminal.set_height(Some(10));
+        type_keys(&mut terminal, b"flood 2000");
+
+        // Far more than can be held back; the command waits for each page in turn
+        terminal.io.type_bytes(&[b' '; 300]);
+        terminal.process_bytes(b"\r");
+        while terminal.output_paused {
+            terminal.process_input();
+        }
+        let output = String::from_utf8(terminal.io.take_output()).unwrap();
+        let output = output.replace("\x1b[7m--More--\x1b[0m\r\x1b[0K", "");
+        let numbers: String = (1..=2000).map(|number| format!("{number}\r\n")).collect();
+        let expected = [b"\r\n", numbers.as_bytes(), &prompt()].concat();
+        assert_eq!(output.as_bytes(), expected);
+    }
+
+    #[test]
+    fn output_is_not_paged_without_a_height() {
+        let mut terminal = terminal();
+        let output = type_keys(&mut terminal, b"count 100\r");
+        assert!(output.ends_with(&[b"100\r\n".as_slice(), &prompt()].concat()));
+    }
+}
@@ -0,0 +1,60 @@
+//! Formatted printing macros for the terminal.
+//!
+//! [`cli_print!`] and [`cli_println!`] format their arguments with `core::fmt`
+//! straight into anything providing `write_styled` and `core::fmt::Write`, i.e. a
+//! [`Terminal`](crate::peripherals::uart::terminal::Terminal) or a
+//! [`Cli`](crate::cli::Cli), without heap allocation.
+//!
+//! An optional level (`error`, `warning` or `ok`) or an explicit `style = ...`
+//! after the target selects the colours of the text:
+//!
+//! ```ignore
+//! cli_println!(terminal, "CHIP_ID = 0x{:08X}", chip_id);
+//! cli_println!(terminal, error, "gpio: pin {} out of range", pin);
+//! cli_print!(terminal, style = Style::new().bold(), "{} Hz", frequency);
+//! ```
+
+/// Prints formatted text, optionally styled by level or explicit style
+macro_rules! cli_print {
+    ($target:expr, error, $($arg:tt)+) => {
+        $target.write_styled(
+            $crate::peripherals::uart::style::Style::ERROR,
+            format_args!($($arg)+),
+        )
+    };
+    ($target:expr, warning, $($arg:tt)+) => {
+        $target.write_styled(
+            $crate::peripherals::uart::style::Style::WARNING,
+            format_args!($($arg)+),
+        )
+    };
+    ($target:expr, ok, $($arg:tt)+) => {
+        $target.write_styled(
+            $crate::peripherals::uart::style::Style::OK,
+            format_args!($($arg)+),
+        )
+    };
+    ($target:expr, style = $style:expr, $($arg:tt)+) => {
+        $target.write_styled($style, format_args!($($arg)+))
+    };
+    ($target:expr, $($arg:tt)+) => {{
+        use core::fmt::Write as _;
+        let _ = $target.write_fmt(format_args!($($arg)+));
+    }};
+}
+
+/// Prints formatted text followed by a newline, optionally styled by level or explicit style
+///
+/// The styling ends before the newline, so the next line starts unstyled.
+macro_rules! cli_println {
+    ($target:expr) => {{
+        use core::fmt::Write as _;
+        let _ = $target.write_str("\n");
+    }};
+    ($target:expr, $($arg:tt)+) => {{
+        $crate::peripherals::uart::print::cli_print!($target, $($arg)+);
+        $crate::peripherals::uart::print::cli_println!($target);
+    }};
+}
+
+pub(crate) use {cli_print, cli_println};
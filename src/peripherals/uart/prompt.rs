@@ -0,0 +1,256 @@
+//! Prompt template module for the terminal line editor.
+//!
+//! A template is prompt text with placeholders that are expanded every time the
+//! prompt is printed:
+//! - `{uptime}` - time since boot as `HH:MM:SS`, prefixed with the days once there are any
+//! - `{status}` - exit status of the last command, highlighted when it failed
+//! - `{uart}` - index of the UART the shell runs on
+//! - `{context}` - user-settable text, e.g. the name of the board
+//!
+//! Anything else, including unknown placeholders, is printed as written.
+
+use crate::constants::{PROMPT_CONTEXT_LENGTH, PROMPT_TEMPLATE_LENGTH};
+use core::fmt::Write;
+use core::ops::Range;
+
+/// Maximum length of an expanded prompt
+const EXPANDED_PROMPT_LENGTH: usize = 2 * PROMPT_TEMPLATE_LENGTH;
+
+/// Text shown for a field whose value is not available
+const UNKNOWN_FIELD: &str = "-";
+
+/// User-settable text shown by the `{context}` placeholder
+pub type Context = heapless::String<PROMPT_CONTEXT_LENGTH>;
+
+/// Live values substituted for the placeholders
+pub struct PromptFields<'a> {
+    /// Time since boot in microseconds, if known
+    pub uptime_us: Option<u64>,
+
+    /// Exit status of the last command, 0 on success
+    pub exit_status: u8,
+
+    /// Index of the UART the shell runs on, if it runs on one
+    pub uart_index: Option<u8>,
+
+    /// User-settable context text
+    pub context: &'a str,
+}
+
+/// A prompt ready to be printed
+#[derive(Default)]
+pub struct ExpandedPrompt {
+    /// Prompt text with all placeholders expanded
+    pub text: heapless::String<EXPANDED_PROMPT_LENGTH>,
+
+    /// Byte range of the text reporting a failure, empty if there is none
+    pub alert: Range<usize>,
+}
+
+impl ExpandedPrompt {
+    /// Creates a prompt that is printed as given, without expanding placeholders
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Prompt text; invalid UTF-8 yields an empty prompt
+    pub fn verbatim(text: &[u8]) -> Self {
+        let mut prompt = ExpandedPrompt::default();
+        if let Ok(text) = core::str::from_utf8(text) {
+            let _ = prompt.text.push_str(text);
+        }
+        prompt
+    }
+
+    /// Places text in front of the prompt
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Text to show before the prompt
+    ///
+    /// # Returns
+    ///
+    /// The combined prompt, or the prompt unchanged if the prefix does not fit
+    pub fn prefixed(self, prefix: &str) -> Self {
+        let mut text = heapless::String::new();
+        if text.push_str(prefix).is_err() || text.push_str(&self.text).is_err() {
+            return self;
+        }
+        let alert = if self.alert.is_empty() {
+            0..0
+        } else {
+            self.alert.start + prefix.len()..self.alert.end + prefix.len()
+        };
+        ExpandedPrompt { text, alert }
+    }
+}
+
+/// Prompt text with placeholders
+#[derive(Default)]
+pub struct PromptTemplate {
+    /// Template text as set by the user
+    template: heapless::String<PROMPT_TEMPLATE_LENGTH>,
+}
+
+impl PromptTemplate {
+    /// Creates a template from its text
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Prompt text with placeholders
+    ///
+    /// # Returns
+    ///
+    /// The template, or `None` if the text is not valid UTF-8 or longer than
+    /// [`PROMPT_TEMPLATE_LENGTH`] bytes
+    pub fn new(template: &[u8]) -> Option<Self> {
+        let template = core::str::from_utf8(template).ok()?;
+        Some(PromptTemplate {
+            template: heapless::String::try_from(template).ok()?,
+        })
+    }
+
+    /// Returns the template text
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Expands the placeholders with the given values
+    ///
+    /// Text that does not fit into the expanded prompt is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `fields` - Values to substitute
+    pub fn expand(&self, fields: &PromptFields) -> ExpandedPrompt {
+        let mut prompt = ExpandedPrompt::default();
+        let text = &mut prompt.text;
+        let mut rest = self.template.as_str();
+
+        while let Some(open) = rest.find('{') {
+            let _ = text.push_str(&rest[..open]);
+            rest = &rest[open..];
+
+            let Some(close) = rest.find('}') else {
+                break;
+            };
+            match &rest[1..close] {
+                "uptime" => match fields.uptime_us {
+                    Some(uptime_us) => write_uptime(text, uptime_us),
+                    None => {
+                        let _ = text.push_str(UNKNOWN_FIELD);
+                    }
+                },
+                "status" => {
+                    let start = text.len();
+                    let _ = write!(text, "{}", fields.exit_status);
+                    if fields.exit_status != 0 {
+                        prompt.alert = start..text.len();
+                    }
+                }
+                "uart" => match fields.uart_index {
+                    Some(index) => {
+                        let _ = write!(text, "{}", index);
+                    }
+                    None => {
+                        let _ = text.push_str(UNKNOWN_FIELD);
+                    }
+                },
+                "context" => {
+                    let _ = text.push_str(fields.context);
+                }
+                _ => {
+                    // Not a placeholder: keep the brace and look for one after it
+                    let _ = text.push('{');
+                    rest = &rest[1..];
+                    continue;
+                }
+            }
+            rest = &rest[close + 1..];
+        }
+
+        let _ = text.push_str(rest);
+        prompt
+    }
+}
+
+/// Appends an uptime as `HH:MM:SS`, prefixed with the number of days once there are any
+///
+/// # Arguments
+///
+/// * `text` - Text to append to
+/// * `uptime_us` - Uptime in microseconds
+pub fn write_uptime(text: &mut impl Write, uptime_us: u64) {
+    let seconds = uptime_us / 1_000_000;
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+    if days > 0 {
+        let _ = write!(text, "{}d ", days);
+    }
+    let _ = write!(
+        text,
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(exit_status: u8) -> PromptFields<'static> {
+        PromptFields {
+            uptime_us: Some(3_723_000_000),
+            exit_status,
+            uart_index: Some(0),
+            context: "bench",
+        }
+    }
+
+    fn expand(template: &str, fields: &PromptFields) -> ExpandedPrompt {
+        PromptTemplate::new(template.as_bytes())
+            .unwrap()
+            .expand(fields)
+    }
+
+    #[test]
+    fn expands_every_field() {
+        let prompt = expand("{context}@uart{uart} {uptime} [{status}]$ ", &fields(0));
+        assert_eq!(prompt.text, "bench@uart0 01:02:03 [0]$ ");
+        assert!(prompt.alert.is_empty());
+
+        let unknown = PromptFields {
+            uptime_us: None,
+            uart_index: None,
+            ..fields(0)
+        };
+        assert_eq!(expand("{uptime} {uart}", &unknown).text, "- -");
+    }
+
+    #[test]
+    fn failed_status_is_marked_for_highlighting() {
+        let prompt = expand("[{status}]$ ", &fields(127));
+        assert_eq!(prompt.text, "[127]$ ");
+        assert_eq!(&prompt.text[prompt.alert.clone()], "127");
+
+        let prompt = prompt.prefixed("(ins) ");
+        assert_eq!(prompt.text, "(ins) [127]$ ");
+        assert_eq!(&prompt.text[prompt.alert.clone()], "127");
+    }
+
+    #[test]
+    fn other_braces_are_kept() {
+        let prompt = expand("{{uart}} {nope} {uart", &fields(0));
+        assert_eq!(prompt.text, "{0} {nope} {uart");
+
+        let mut text = heapless::String::<16>::new();
+        write_uptime(&mut text, 2 * 86_400_000_000 + 59_000_000);
+        assert_eq!(text, "2d 00:00:59");
+    }
+
+    #[test]
+    fn rejects_templates_that_do_not_fit() {
+        assert!(PromptTemplate::new(&[b'x'; PROMPT_TEMPLATE_LENGTH]).is_some());
+        assert!(PromptTemplate::new(&[b'x'; PROMPT_TEMPLATE_LENGTH + 1]).is_none());
+    }
+}
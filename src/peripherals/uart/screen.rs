@@ -0,0 +1,343 @@
+//! Screen frame module for full-screen terminal views.
+//!
+//! A [`Frame`] holds the characters of every cell of a fixed-size screen. Views draw
+//! a complete frame each time they are refreshed, and the terminal compares it with
+//! the frame shown before so that only the cells that changed are sent.
+//!
+//! The terminal methods that open, refresh and close views are implemented here as well.
+
+use crate::constants::{VIEW_COLUMNS, VIEW_REFRESH_US, VIEW_ROWS};
+use crate::peripherals::timer::uptime_us;
+use crate::peripherals::uart::ByteIo;
+use crate::peripherals::uart::terminal::{ASCIIControl, Terminal};
+use crate::peripherals::uart::widgets::{Form, FormHandler, FormKey};
+use core::fmt;
+use core::ops::Range;
+
+/// Character shown in place of characters that cannot be drawn in a cell
+const REPLACEMENT: u8 = b'?';
+
+/// Characters of every cell of a full-screen view
+#[derive(Clone)]
+pub struct Frame {
+    /// Rows of cells, each holding a printable ASCII character
+    cells: [[u8; VIEW_COLUMNS]; VIEW_ROWS],
+}
+
+impl Frame {
+    /// Creates a blank frame
+    pub fn new() -> Self {
+        Frame {
+            cells: [[b' '; VIEW_COLUMNS]; VIEW_ROWS],
+        }
+    }
+
+    /// Blanks every cell
+    pub fn clear(&mut self) {
+        self.cells = [[b' '; VIEW_COLUMNS]; VIEW_ROWS];
+    }
+
+    /// Returns a writer placing text into the frame from a cell onwards
+    ///
+    /// Text is cut off at the end of the row. Characters other than printable ASCII
+    /// are drawn as `?`.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row of the first cell, counted from 0
+    /// * `column` - Column of the first cell, counted from 0
+    pub fn at(&mut self, row: usize, column: usize) -> FrameWriter<'_> {
+        FrameWriter {
+            frame: self,
+            row,
+            column,
+        }
+    }
+
+    /// Returns the cells of a row
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row counted from 0, below [`VIEW_ROWS`]
+    pub fn row(&self, row: usize) -> &[u8] {
+        &self.cells[row]
+    }
+
+    /// Finds the next run of cells in a row that differ from another frame
+    ///
+    /// # Arguments
+    ///
+    /// * `shown` - Frame currently on the screen
+    /// * `row` - Row to compare, counted from 0
+    /// * `from` - Column to start searching at
+    /// * `columns` - Number of columns visible on the screen
+    ///
+    /// # Returns
+    ///
+    /// The columns of the run, or `None` if the rest of the row is unchanged
+    pub fn changed_run(
+        &self,
+        shown: &Frame,
+        row: usize,
+        from: usize,
+        columns: usize,
+    ) -> Option<Range<usize>> {
+        let columns = columns.min(VIEW_COLUMNS);
+        let (new, old) = (self.cells.get(row)?, &shown.cells[row]);
+        let differs = |column: &usize| new[*column] != old[*column];
+
+        let start = (from..columns).find(differs)?;
+        let end = (start..columns)
+            .find(|column| !differs(column))
+            .unwrap_or(columns);
+        Some(start..end)
+    }
+}
+
+/// Writer placing formatted text into a [`Frame`], see [`Frame::at`]
+pub struct FrameWriter<'a> {
+    /// Frame written to
+    frame: &'a mut Frame,
+
+    /// Row written to
+    row: usize,
+
+    /// Column of the next character
+    column: usize,
+}
+
+impl fmt::Write for FrameWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let Some(cells) = self.frame.cells.get_mut(self.row) else {
+            return Ok(());
+        };
+
+        for character in s.chars() {
+            let Some(cell) = cells.get_mut(self.column) else {
+                break;
+            };
+            *cell = match character {
+                ' '..='~' => character as u8,
+                _ => REPLACEMENT,
+            };
+            self.column += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Full-screen view shown with [`Terminal::show_view`] or [`Terminal::show_form`]
+pub(super) struct View<IO: ByteIo> {
+    /// What the view shows
+    pub(super) content: ViewContent<IO>,
+
+    /// Frame the view is drawn into
+    frame: Frame,
+
+    /// Frame currently on the screen
+    shown: Frame,
+
+    /// Uptime at which the view is redrawn next, if the uptime is known
+    next_refresh_us: Option<u64>,
+}
+
+/// What a full-screen view shows
+// Without a heap the form is stored inline; the frames of the view are larger still
+#[allow(clippy::large_enum_variant)]
+pub(super) enum ViewContent<IO: ByteIo> {
+    /// Drawing redrawn periodically until any key is pressed
+    Live(DrawView<IO>),
+
+    /// Form edited with the keyboard, and the function receiving it once finished
+    Form(Form, FormHandler<IO>),
+}
+
+/// Signature of a function drawing a view shown with [`Terminal::show_view`]
+///
+/// The function receives a blank frame and draws the complete view into it.
+pub type DrawView<IO> = fn(terminal: &Terminal<IO>, frame: &mut Frame);
+
+impl<IO: ByteIo> Terminal<IO> {
+    /// Replaces the prompt with a full-screen view until a key is pressed
+    ///
+    /// Intended for command handlers. The screen is cleared and `draw` called to fill a
+    /// frame, which is redrawn every [`VIEW_REFRESH_US`] from [`Self::process_input`];
+    /// only the cells that changed since the previous frame are sent. Any key clears the
+    /// screen and returns to the prompt, and is not processed any further.
+    ///
+    /// # Arguments
+    ///
+    /// * `draw` - Function drawing the view; frames are [`VIEW_ROWS`] by [`VIEW_COLUMNS`]
+    ///   cells, cut to the terminal size
+    ///
+    /// [`VIEW_COLUMNS`]: crate::constants::VIEW_COLUMNS
+    pub fn show_view(&mut self, draw: DrawView<IO>) {
+        if self.plain {
+            self.report_plain("full-screen views");
+            return;
+        }
+        self.open_view(ViewContent::Live(draw));
+    }
+
+    /// Clears the screen and shows a full-screen view
+    pub(super) fn open_view(&mut self, content: ViewContent<IO>) {
+        self.print_control_sequence(ASCIIControl::HideCursor.as_bytes());
+        // The view covers the status bar until it is closed
+        if self.status_bar_layout().is_some() {
+            self.print_control_sequence(ASCIIControl::ResetScrollRegion.as_bytes());
+        }
+        self.view = Some(View {
+            content,
+            frame: Frame::new(),
+            shown: Frame::new(),
+            next_refresh_us: None,
+        });
+        self.clear_screen();
+        self.redraw_view();
+    }
+
+    /// Redraws a live view if it is due
+    ///
+    /// Without a timer, the view is redrawn on every call.
+    fn refresh_view(&mut self) {
+        let Some(view) = &self.view else {
+            return;
+        };
+        if let (Some(now), Some(next)) = (uptime_us(), view.next_refresh_us)
+            && now < next
+        {
+            return;
+        }
+        self.redraw_view();
+    }
+
+    /// Draws the full-screen view, sending only the cells that changed
+    pub(super) fn redraw_view(&mut self) {
+        let Some(mut view) = self.view.take() else {
+            return;
+        };
+        view.next_refresh_us = uptime_us().map(|now| now + VIEW_REFRESH_US);
+
+        view.frame.clear();
+        match &view.content {
+            ViewContent::Live(draw) => draw(self, &mut view.frame),
+            ViewContent::Form(form, _) => form.render(&mut view.frame),
+        }
+
+        let rows = self.height.unwrap_or(VIEW_ROWS).min(VIEW_ROWS);
+        for row in 0..rows {
+            let mut from = 0;
+            while let Some(run) = view.frame.changed_run(&view.shown, row, from, self.width) {
+                self.print_cursor_position(row, run.start);
+                self.io.print(&view.frame.row(row)[run.clone()]);
+                from = run.end;
+            }
+        }
+        core::mem::swap(&mut view.frame, &mut view.shown);
+        self.view = Some(view);
+    }
+
+    /// Leaves the full-screen view and returns to the prompt
+    fn close_view(&mut self) {
+        self.leave_view();
+        self.print_prompt();
+    }
+
+    /// Leaves the full-screen view, leaving a cleared screen
+    pub(super) fn leave_view(&mut self) {
+        self.view = None;
+        self.clear_screen();
+        self.print_control_sequence(ASCIIControl::ShowCursor.as_bytes());
+    }
+
+    /// Passes keys to the form shown, or leaves a live view on any key and otherwise
+    /// redraws it when due
+    ///
+    /// Log messages stay queued until the prompt is back.
+    pub(super) fn process_view_input(&mut self) {
+        let held = core::mem::take(&mut self.held_input);
+        if self.is_form_shown() {
+            self.process_form_bytes(&held);
+            let bytes = self.io.get_input();
+            self.process_form_bytes(&bytes);
+            if self.is_form_shown() && self.take_expired_escape() {
+                self.process_form_key(FormKey::Escape);
+            }
+        } else if !held.is_empty() || !self.io.get_input().is_empty() {
+            self.close_view();
+        } else {
+            self.refresh_view();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::uart::fake::{FRAMES_DRAWN, prompt, terminal, type_keys};
+    use core::fmt::Write;
+
+    #[test]
+    fn text_is_placed_and_clipped() {
+        let mut frame = Frame::new();
+        let _ = write!(frame.at(1, VIEW_COLUMNS - 3), "{}°C", 21);
+        assert_eq!(&frame.row(1)[VIEW_COLUMNS - 4..], b" 21?");
+
+        // Rows beyond the frame are ignored
+        let _ = write!(frame.at(VIEW_ROWS, 0), "lost");
+        frame.clear();
+        assert!(frame.row(1).iter().all(|&cell| cell == b' '));
+    }
+
+    #[test]
+    fn finds_runs_of_changed_cells() {
+        let shown = Frame::new();
+        let mut frame = Frame::new();
+        let _ = write!(frame.at(0, 2), "ab");
+        let _ = write!(frame.at(0, 6), "c");
+
+        assert_eq!(frame.changed_run(&shown, 0, 0, 80), Some(2..4));
+        assert_eq!(frame.changed_run(&shown, 0, 4, 80), Some(6..7));
+        assert_eq!(frame.changed_run(&shown, 0, 7, 80), None);
+        // Columns past the screen width are not compared
+        assert_eq!(frame.changed_run(&shown, 0, 4, 6), None);
+        assert_eq!(frame.changed_run(&shown, 1, 0, 80), None);
+    }
+
+    #[test]
+    fn views_redraw_changed_cells_until_a_key_is_pressed() {
+        FRAMES_DRAWN.store(0, core::sync::atomic::Ordering::Relaxed);
+        let mut terminal = terminal();
+
+        // The view replaces the prompt
+        let output = type_keys(&mut terminal, b"watch\r");
+        // Blank cells match the cleared screen and are skipped
+        assert!(output.ends_with(b"\r\n\x1b[?25l\x1b[2J\x1b[H\x1b[2;3Hframe\x1b[2;9H1"));
+
+        // Without a timer, every poll redraws, sending only the changed cell
+        terminal.process_input();
+        assert_eq!(terminal.io.take_output(), b"\x1b[2;9H2");
+
+        // Any key returns to the prompt without being processed further
+        terminal.io.type_bytes(b"x");
+        terminal.process_input();
+        let expected = [b"\x1b[2J\x1b[H\x1b[?25h".as_slice(), &prompt()].concat();
+        assert_eq!(terminal.io.take_output(), expected);
+        assert!(terminal.current_line.is_empty());
+    }
+
+    #[test]
+    fn views_are_cut_to_the_terminal_size() {
+        let mut terminal = terminal();
+        terminal.set_width(5);
+        terminal.set_height(Some(2));
+        terminal.show_view(|_terminal, frame| {
+            let _ = fmt::Write::write_str(&mut frame.at(1, 2), "hello");
+            let _ = fmt::Write::write_str(&mut frame.at(2, 0), "hidden");
+        });
+        assert_eq!(
+            terminal.io.take_output(),
+            b"\x1b[?25l\x1b[2J\x1b[H\x1b[2;3Hhel"
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Status bar module for a row kept free of scrolling output.
+//!
+//! The terminal reserves the top or bottom row of the screen for a status bar by
+//! limiting scrolling to the other rows with a DECSTBM scroll region
+//! (`ESC [ top ; bottom r`). Command output then scrolls beneath the bar, which stays
+//! in place and is redrawn whenever its text changes.
+//!
+//! The terminal methods that set up the scroll region and draw the bar live here too.
+
+use crate::constants::{STATUS_REFRESH_US, VIEW_COLUMNS};
+use crate::peripherals::timer::uptime_us;
+use crate::peripherals::uart::ByteIo;
+use crate::peripherals::uart::style::Style;
+use crate::peripherals::uart::terminal::{ASCIICode, ASCIIControl, Terminal};
+
+/// Text shown in the status bar
+pub type StatusText = heapless::String<VIEW_COLUMNS>;
+
+/// Edge of the screen the status bar is pinned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusPosition {
+    /// First row of the screen
+    Top,
+
+    /// Last row of the screen
+    Bottom,
+}
+
+impl StatusPosition {
+    /// Returns the row the bar is drawn on
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - Number of rows of the terminal window
+    ///
+    /// # Returns
+    ///
+    /// The row, counted from 0
+    pub fn bar_row(&self, rows: usize) -> usize {
+        match self {
+            StatusPosition::Top => 0,
+            StatusPosition::Bottom => rows.saturating_sub(1),
+        }
+    }
+
+    /// Returns the rows output scrolls in, leaving the bar out
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - Number of rows of the terminal window
+    ///
+    /// # Returns
+    ///
+    /// The first and last row of the scroll region, counted from 1 as DECSTBM expects
+    pub fn scroll_region(&self, rows: usize) -> (usize, usize) {
+        match self {
+            StatusPosition::Top => (2, rows),
+            StatusPosition::Bottom => (1, rows.saturating_sub(1)),
+        }
+    }
+
+    /// Returns the row output starts on once the screen is cleared
+    ///
+    /// # Returns
+    ///
+    /// The first row of the scroll region, counted from 0
+    pub fn first_output_row(&self) -> usize {
+        match self {
+            StatusPosition::Top => 1,
+            StatusPosition::Bottom => 0,
+        }
+    }
+}
+
+/// Status bar enabled with [`Terminal::show_status_bar`]
+pub(super) struct StatusBar<IO: ByteIo> {
+    /// Edge of the screen the bar is pinned to
+    position: StatusPosition,
+
+    /// Function writing the text of the bar
+    draw: DrawStatus<IO>,
+
+    /// Number of rows the scroll region was set for, once the terminal answered the
+    /// query sent when the bar was enabled
+    pub(super) rows: Option<usize>,
+
+    /// Text currently shown in the bar
+    pub(super) shown: StatusText,
+
+    /// Uptime at which the bar is updated next, if the uptime is known
+    next_refresh_us: Option<u64>,
+}
+
+/// Signature of a function writing the text of a status bar shown with
+/// [`Terminal::show_status_bar`]
+///
+/// The function receives empty text; whatever does not fit is cut off.
+pub type DrawStatus<IO> = fn(terminal: &Terminal<IO>, text: &mut StatusText);
+
+impl<IO: ByteIo> Terminal<IO> {
+    /// Pins a status bar to the top or bottom row of the screen
+    ///
+    /// The bar needs the number of rows of the terminal window, so the size is queried
+    /// with [`Self::request_size`]. Once the terminal answers, the screen is cleared,
+    /// output is limited to the other rows with a scroll region and the bar is drawn; it
+    /// is then updated every [`STATUS_REFRESH_US`] when its text changes. A terminal that
+    /// does not answer within [`SIZE_QUERY_TIMEOUT_US`](crate::constants::SIZE_QUERY_TIMEOUT_US) is taken not to support scroll
+    /// regions either, and the bar is disabled again with a warning. Plain output cannot
+    /// show a status bar.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Edge of the screen to pin the bar to
+    /// * `draw` - Function writing the text of the bar
+    pub fn show_status_bar(&mut self, position: StatusPosition, draw: DrawStatus<IO>) {
+        if self.plain {
+            self.report_plain("status bars");
+            return;
+        }
+        self.hide_status_bar();
+        self.status_bar = Some(StatusBar {
+            position,
+            draw,
+            rows: None,
+            shown: StatusText::new(),
+            next_refresh_us: None,
+        });
+        self.request_size();
+    }
+
+    /// Removes the status bar, letting output use the whole screen again
+    pub fn hide_status_bar(&mut self) {
+        let layout = self.status_bar_layout();
+        self.status_bar = None;
+        let Some((position, rows)) = layout else {
+            return;
+        };
+
+        // Setting a scroll region moves the cursor, so it is saved around the changes
+        self.io.putc(ASCIICode::Escape as u8);
+        self.io.putc(ASCIICode::SaveCursor as u8);
+        self.print_control_sequence(ASCIIControl::ResetScrollRegion.as_bytes());
+        self.print_cursor_position(position.bar_row(rows), 0);
+        self.print_control_sequence(ASCIIControl::ClearToEndOfLine.as_bytes());
+        self.io.putc(ASCIICode::Escape as u8);
+        self.io.putc(ASCIICode::RestoreCursor as u8);
+    }
+
+    /// Returns the edge of the screen the status bar is pinned to, while it is shown
+    pub fn status_bar_position(&self) -> Option<StatusPosition> {
+        self.status_bar_layout().map(|(position, _)| position)
+    }
+
+    /// Returns the position of the status bar and the rows of the screen, while the bar
+    /// is active and not covered by a full-screen view
+    pub(super) fn status_bar_layout(&self) -> Option<(StatusPosition, usize)> {
+        if self.view.is_some() {
+            return None;
+        }
+        let bar = self.status_bar.as_ref()?;
+        Some((bar.position, bar.rows?))
+    }
+
+    /// Returns the number of rows available to output, leaving out the status bar
+    pub(super) fn output_rows(&self) -> Option<usize> {
+        let bar_rows = usize::from(self.status_bar_layout().is_some());
+        self.height.map(|height| height.saturating_sub(bar_rows))
+    }
+
+    /// Sets up the status bar once the terminal answered the size query
+    ///
+    /// The screen is cleared to make room for the bar, and the line being edited
+    /// redrawn below it.
+    pub(super) fn activate_status_bar(&mut self) {
+        let height = self.height;
+        let Some(bar) = &mut self.status_bar else {
+            return;
+        };
+        bar.rows = height;
+        self.redraw_screen();
+    }
+
+    /// Redraws the status bar if its text changed
+    ///
+    /// # Arguments
+    ///
+    /// * `force` - Whether to redraw the bar even if its text did not change
+    pub(super) fn draw_status_bar(&mut self, force: bool) {
+        let Some((position, rows)) = self.status_bar_layout() else {
+            return;
+        };
+        let Some(bar) = &self.status_bar else {
+            return;
+        };
+
+        let mut text = StatusText::new();
+        (bar.draw)(self, &mut text);
+        if !force && text == bar.shown {
+            return;
+        }
+
+        // Pad the bar to the full width, cutting off text that does not fit
+        let columns = self.width.min(text.capacity());
+        let mut cells = 0;
+        let mut end = 0;
+        for (index, character) in text.char_indices() {
+            if cells == columns {
+                break;
+            }
+            cells += 1;
+            end = index + character.len_utf8();
+        }
+
+        self.io.putc(ASCIICode::Escape as u8);
+        self.io.putc(ASCIICode::SaveCursor as u8);
+        self.print_cursor_position(position.bar_row(rows), 0);
+        self.apply_style(Style::new().reverse());
+        self.io.print(&text.as_bytes()[..end]);
+        for _ in cells..columns {
+            self.io.putc(ASCIICode::Space as u8);
+        }
+        self.clear_formatting();
+        self.io.putc(ASCIICode::Escape as u8);
+        self.io.putc(ASCIICode::RestoreCursor as u8);
+
+        if let Some(bar) = &mut self.status_bar {
+            bar.shown = text;
+        }
+    }
+
+    /// Updates the status bar when it is due
+    ///
+    /// Without a timer, the bar is checked on every call.
+    pub(super) fn refresh_status_bar(&mut self) {
+        let now = uptime_us();
+        let Some(bar) = &mut self.status_bar else {
+            return;
+        };
+        if let (Some(now), Some(next)) = (now, bar.next_refresh_us)
+            && now < next
+        {
+            return;
+        }
+        bar.next_refresh_us = now.map(|now| now + STATUS_REFRESH_US);
+        self.draw_status_bar(false);
+    }
+
+    /// Clears the screen from the cursor to the end with `ESC [ 0 J`
+    ///
+    /// The scroll region does not limit the clear, so a status bar at the bottom of the
+    /// screen is wiped as well and drawn again.
+    pub(super) fn clear_to_end_of_screen(&mut self) {
+        self.print_control_sequence(ASCIIControl::ClearToEndOfScreen.as_bytes());
+        if self.status_bar_position() == Some(StatusPosition::Bottom) {
+            self.draw_status_bar(true);
+        }
+    }
+
+    /// Limits scrolling to a range of rows with `ESC [ top ; bottom r` (DECSTBM)
+    ///
+    /// Terminals move the cursor to the top-left corner when the region is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `top` - First row of the region, counted from 1
+    /// * `bottom` - Last row of the region, counted from 1
+    pub(super) fn print_scroll_region(&mut self, top: usize, bottom: usize) {
+        let mut buffer = itoa::Buffer::new();
+        self.print_escape_sequence();
+        self.io.print(buffer.format(top).as_bytes());
+        self.io.putc(ASCIICode::Semicolon as u8);
+        self.io.print(buffer.format(bottom).as_bytes());
+        self.io.putc(ASCIICode::ScrollRegion as u8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::uart::fake::{draw_status, prompt, terminal, type_keys};
+
+    #[test]
+    fn scroll_region_leaves_out_the_bar() {
+        assert_eq!(StatusPosition::Top.bar_row(24), 0);
+        assert_eq!(StatusPosition::Top.scroll_region(24), (2, 24));
+        assert_eq!(StatusPosition::Top.first_output_row(), 1);
+
+        assert_eq!(StatusPosition::Bottom.bar_row(24), 23);
+        assert_eq!(StatusPosition::Bottom.scroll_region(24), (1, 23));
+        assert_eq!(StatusPosition::Bottom.first_output_row(), 0);
+    }
+
+    #[test]
+    fn status_bar_is_set_up_once_the_terminal_answers() {
+        let mut terminal = terminal();
+        terminal.set_width(6);
+        terminal.show_status_bar(StatusPosition::Bottom, draw_status);
+        assert_eq!(terminal.io.take_output(), b"\x1b7\x1b[999;999H\x1b[6n\x1b8");
+        assert_eq!(terminal.status_bar_position(), None);
+        type_keys(&mut terminal, b"ab");
+
+        // The answer sets the scroll region, draws the bar and redraws the line above it
+        let bar = b"\x1b[2J\x1b[1;9r\x1b7\x1b[10;1H\x1b[7mok    \x1b[0m\x1b8\x1b[1;1H";
+        // Clearing the rows of the line also wipes the bar, so it is drawn again
+        let redrawn = b"\r\x1b[0J\x1b7\x1b[10;1H\x1b[7mok    \x1b[0m\x1b8";
+        let expected = [bar.as_slice(), redrawn, &prompt(), b"ab"].concat();
+        assert_eq!(type_keys(&mut terminal, b"\x1b[10;6R"), expected);
+        assert_eq!(terminal.status_bar_position(), Some(StatusPosition::Bottom));
+
+        // The bar is only redrawn when its text changes
+        terminal.process_input();
+        assert_eq!(terminal.io.take_output(), b"");
+
+        // The bar survives clearing the screen
+        terminal.clear_screen();
+        assert_eq!(terminal.io.take_output(), bar);
+
+        // And messages printed above the line
+        terminal.print_above(b"tick");
+        let expected = [redrawn.as_slice(), b"tick\x1b[0m\r\n", &prompt()].concat();
+        assert_eq!(terminal.io.take_output(), expected);
+
+        terminal.hide_status_bar();
+        assert_eq!(
+            terminal.io.take_output(),
+            b"\x1b7\x1b[r\x1b[10;1H\x1b[0K\x1b8"
+        );
+        assert_eq!(terminal.status_bar_position(), None);
+    }
+
+    #[test]
+    fn status_bar_is_disabled_without_an_answer() {
+        let mut terminal = terminal();
+        terminal.show_status_bar(StatusPosition::Top, draw_status);
+        terminal.io.take_output();
+
+        terminal.process_input();
+        let warning = b"\x1b[33mstatus bar: no answer from the terminal, status bar disabled";
+        assert!(
+            terminal
+                .io
+                .take_output()
+                .windows(warning.len())
+                .any(|window| window == warning)
+        );
+
+        // A late answer only updates the size
+        type_keys(&mut terminal, b"\x1b[10;6R");
+        assert_eq!(terminal.status_bar_position(), None);
+    }
+}
@@ -0,0 +1,284 @@
+//! Text styling module for terminal output.
+//!
+//! Styles are rendered as ECMA-48 Select Graphic Rendition (SGR) sequences and
+//! support:
+//! - The 16 standard foreground and background colours
+//! - 256-colour palette indices
+//! - 24-bit truecolor
+//! - Bold, dim, underline and reverse video attributes
+
+/// Maximum length of a rendered SGR sequence: all four attributes plus two
+/// truecolor parameter groups
+const MAX_SGR_LENGTH: usize = 48;
+
+/// A terminal colour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+
+    /// Entry of the 256-colour palette
+    Indexed(u8),
+
+    /// 24-bit colour given as red, green and blue components
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Appends the SGR parameters selecting this colour
+    ///
+    /// # Arguments
+    ///
+    /// * `sgr` - Sequence being built
+    /// * `background` - Whether to select the background rather than the foreground colour
+    fn push_params(&self, sgr: &mut Sgr, background: bool) {
+        // Offsets from the foreground codes (30-37, 90-97, 38) to the background ones
+        let offset = if background { 10 } else { 0 };
+        match *self {
+            Color::Indexed(index) => {
+                sgr.param(38 + offset);
+                sgr.param(5);
+                sgr.param(index.into());
+            }
+            Color::Rgb(red, green, blue) => {
+                sgr.param(38 + offset);
+                sgr.param(2);
+                sgr.param(red.into());
+                sgr.param(green.into());
+                sgr.param(blue.into());
+            }
+            standard => {
+                let index = standard.standard_index();
+                if index < 8 {
+                    sgr.param(30 + offset + index);
+                } else {
+                    sgr.param(90 + offset + index - 8);
+                }
+            }
+        }
+    }
+
+    /// Returns the position of a standard colour in the 16-colour palette
+    fn standard_index(&self) -> u16 {
+        match self {
+            Color::Black => 0,
+            Color::Red => 1,
+            Color::Green => 2,
+            Color::Yellow => 3,
+            Color::Blue => 4,
+            Color::Magenta => 5,
+            Color::Cyan => 6,
+            Color::White => 7,
+            Color::BrightBlack => 8,
+            Color::BrightRed => 9,
+            Color::BrightGreen => 10,
+            Color::BrightYellow => 11,
+            Color::BrightBlue => 12,
+            Color::BrightMagenta => 13,
+            Color::BrightCyan => 14,
+            Color::BrightWhite => 15,
+            Color::Indexed(_) | Color::Rgb(..) => 0,
+        }
+    }
+}
+
+/// Colours and attributes applied to a run of text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    /// Text colour, or `None` for the terminal default
+    foreground: Option<Color>,
+
+    /// Background colour, or `None` for the terminal default
+    background: Option<Color>,
+
+    bold: bool,
+    dim: bool,
+    underline: bool,
+    reverse: bool,
+}
+
+#[allow(unused)]
+impl Style {
+    /// Style for error messages
+    pub const ERROR: Style = Style::new().foreground(Color::Red);
+
+    /// Style for warnings and usage hints
+    pub const WARNING: Style = Style::new().foreground(Color::Yellow);
+
+    /// Style for success messages
+    pub const OK: Style = Style::new().foreground(Color::Green);
+
+    /// Creates a style that leaves the text unchanged
+    pub const fn new() -> Self {
+        Style {
+            foreground: None,
+            background: None,
+            bold: false,
+            dim: false,
+            underline: false,
+            reverse: false,
+        }
+    }
+
+    /// Returns this style with the given text colour
+    pub const fn foreground(mut self, color: Color) -> Self {
+        self.foreground = Some(color);
+        self
+    }
+
+    /// Returns this style with the given background colour
+    pub const fn background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// Returns this style with bold text
+    pub const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    /// Returns this style with dim text
+    pub const fn dim(mut self) -> Self {
+        self.dim = true;
+        self
+    }
+
+    /// Returns this style with underlined text
+    pub const fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    /// Returns this style with foreground and background swapped
+    pub const fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    /// Returns whether the style changes nothing
+    pub fn is_plain(&self) -> bool {
+        *self == Style::new()
+    }
+
+    /// Renders the style as an SGR sequence `ESC [ params m`
+    ///
+    /// # Returns
+    ///
+    /// The escape sequence, empty for a plain style
+    pub fn to_sgr(self) -> heapless::Vec<u8, MAX_SGR_LENGTH> {
+        let mut sgr = Sgr::new();
+        if self.is_plain() {
+            return sgr.bytes;
+        }
+
+        for (enabled, code) in [
+            (self.bold, 1),
+            (self.dim, 2),
+            (self.underline, 4),
+            (self.reverse, 7),
+        ] {
+            if enabled {
+                sgr.param(code);
+            }
+        }
+        if let Some(color) = self.foreground {
+            color.push_params(&mut sgr, false);
+        }
+        if let Some(color) = self.background {
+            color.push_params(&mut sgr, true);
+        }
+
+        let _ = sgr.bytes.push(b'm');
+        sgr.bytes
+    }
+}
+
+/// Builder for the parameter list of an SGR sequence
+struct Sgr {
+    /// Sequence rendered so far, starting with `ESC [`
+    bytes: heapless::Vec<u8, MAX_SGR_LENGTH>,
+}
+
+impl Sgr {
+    /// Starts an empty sequence
+    fn new() -> Self {
+        Sgr {
+            bytes: heapless::Vec::new(),
+        }
+    }
+
+    /// Appends a numeric parameter, separated from the previous one by `;`
+    fn param(&mut self, value: u16) {
+        if self.bytes.is_empty() {
+            let _ = self.bytes.extend_from_slice(b"\x1b[");
+        } else {
+            let _ = self.bytes.push(b';');
+        }
+
+        let mut buffer = itoa::Buffer::new();
+        let _ = self
+            .bytes
+            .extend_from_slice(buffer.format(value).as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_standard_colours_and_attributes() {
+        assert_eq!(Style::ERROR.to_sgr(), b"\x1b[31m");
+        assert_eq!(
+            Style::new()
+                .foreground(Color::BrightCyan)
+                .background(Color::Blue)
+                .bold()
+                .underline()
+                .to_sgr(),
+            b"\x1b[1;4;96;44m"
+        );
+        assert_eq!(Style::new().dim().reverse().to_sgr(), b"\x1b[2;7m");
+    }
+
+    #[test]
+    fn renders_palette_and_truecolor() {
+        assert_eq!(
+            Style::new().foreground(Color::Indexed(208)).to_sgr(),
+            b"\x1b[38;5;208m"
+        );
+        assert_eq!(
+            Style::new()
+                .bold()
+                .dim()
+                .underline()
+                .reverse()
+                .foreground(Color::Rgb(255, 255, 255))
+                .background(Color::Rgb(255, 128, 0))
+                .to_sgr(),
+            b"\x1b[1;2;4;7;38;2;255;255;255;48;2;255;128;0m"
+        );
+    }
+
+    #[test]
+    fn plain_style_renders_nothing() {
+        assert!(Style::new().is_plain());
+        assert!(Style::new().to_sgr().is_empty());
+    }
+}
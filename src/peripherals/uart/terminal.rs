@@ -1,35 +1,133 @@
 //! Terminal module providing line editing capabilities for UART-based CLI.
 //!
+//! The terminal is generic over a [`ByteIo`] transport so the line editor can be
+//! driven by the UART on the target or by an in-memory fake in host tests.
+//!
 //! This module implements a terminal with support for:
-//! - Basic cursor movement (left/right arrows)
-//! - Text insertion and deletion
-//! - Color-coded prompts
-//! - ANSI escape sequence handling
+//! - Basic cursor movement (left/right arrows, Home/End)
+//! - Minimal redraws that only rewrite the changed part of the line
+//! - Soft-wrapping of lines longer than the terminal width, which is queried from the
+//!   terminal or set by the user
+//! - Paging of command output longer than the terminal height with a `--More--` prompt
+//! - Command history recall (up/down arrows) and reverse incremental search (Ctrl-R)
+//! - Text insertion and deletion, including multi-byte UTF-8 characters
+//! - Emacs-style control-key editing (Ctrl-A/E/K/U/W/L/C/D)
+//! - An optional vi editing mode, switchable at runtime, with Esc entering normal mode
+//!   and the current mode shown in front of the prompt
+//! - Word motion and deletion (Alt-B/F/D, Alt-Backspace, Ctrl-Left/Right) with
+//!   configurable word separators
+//! - Undo/redo (Ctrl-_ / Alt-_) and a kill ring (Ctrl-Y / Alt-Y)
+//! - Tab completion of command names and arguments
+//! - Styled output (colours and text attributes) that can be switched off globally
+//! - Plain output without any escape sequences, for dumb terminals and log captures
+//! - Formatted output through [`core::fmt::Write`] and the [`cli_print!`] macros
+//! - ANSI escape sequence handling (CSI and SS3 key sequences)
 //! - CLI banner display
+//! - A prompt template with live fields (uptime, exit status, UART index, context)
+//! - Command dispatch on Enter
+//! - Printing log messages above the prompt without disturbing the line being edited
+//! - Bracketed paste, applying pasted text as a unit and reporting anything that is lost
+//! - Configurable input line endings and output newlines
+//! - Lines requested by command handlers, optionally masked or hidden for secrets
+//! - Full-screen views that replace the prompt, redrawn in place until a key is pressed
+//! - Full-screen forms of menus, checkboxes and number fields, see
+//!   [`crate::peripherals::uart::widgets`]
+//! - A status bar pinned to the top or bottom row with a scroll region, kept off on
+//!   terminals that do not answer queries
+//!
+//! Paging, views, forms with requested lines and the status bar are driven by methods
+//! implemented in [`crate::peripherals::uart::pager`], [`crate::peripherals::uart::screen`],
+//! [`crate::peripherals::uart::widgets`] and [`crate::peripherals::uart::status_bar`].
+//!
+//! [`cli_print!`]: crate::peripherals::uart::print::cli_print
+
+use crate::cli::commands::{Command, completion_candidates, find_command};
+use crate::constants::{
+    DEFAULT_TERMINAL_WIDTH, ESCAPE_TIMEOUT_US, MAX_COMMAND_ARGS, MAX_LINE_LENGTH,
+    MAX_WORD_SEPARATORS, PAGED_OUTPUT_SIZE, RX_BUFFER_SIZE, SIZE_QUERY_TIMEOUT_US,
+};
+use crate::peripherals::timer::uptime_us;
+use crate::peripherals::uart::escape::{
+    EscapeParser, EscapeSequence, Key, KeyEvent, ParseResult, SequenceKind,
+};
+use crate::peripherals::uart::history::{History, Line, wipe};
+use crate::peripherals::uart::kill_ring::KillRing;
+use crate::peripherals::uart::pager::{OutputStep, Pager};
+use crate::peripherals::uart::print::cli_println;
+use crate::peripherals::uart::prompt::{Context, ExpandedPrompt, PromptFields, PromptTemplate};
+use crate::peripherals::uart::screen::View;
+use crate::peripherals::uart::status_bar::StatusBar;
+use crate::peripherals::uart::style::Style;
+use crate::peripherals::uart::undo::UndoJournal;
+use crate::peripherals::uart::utf8::{
+    DecodeResult, Utf8Decoder, char_count, is_boundary, next_boundary, previous_boundary,
+    wrapped_offset,
+};
+use crate::peripherals::uart::vi::{Motion, ViCommand, ViMode, ViState};
+use crate::peripherals::uart::widgets::LineRequest;
+use crate::peripherals::uart::{ByteIo, UartErrors, UartSettings};
+use core::fmt;
+use core::ops::Range;
+
+/// Characters that end a word for word motion and deletion by default
+///
+/// Besides spaces, punctuation common in command arguments splits words, so that
+/// e.g. each value of `0x2000,0x40` or each part of `GP25=high` is a word of its own.
+pub const DEFAULT_WORD_SEPARATORS: &[u8] = b" \t,.:;=/";
+
+/// Exit status of a command that failed
+pub const EXIT_FAILURE: u8 = 1;
+
+/// Exit status of a command invoked with invalid arguments
+pub const EXIT_USAGE: u8 = 2;
+
+/// Exit status of a line naming an unknown command
+const EXIT_UNKNOWN_COMMAND: u8 = 127;
 
-use crate::constants::MAX_LINE_LENGTH;
-use crate::peripherals::uart::terminal::EscapeState::{BracketReceived, NotReceived, Received};
-use crate::peripherals::uart::{SerialPort, Uart};
-use rp2040_pac::{RESETS, UART0};
+/// Exit status of a line abandoned with Ctrl-C
+const EXIT_INTERRUPTED: u8 = 130;
 
 /// ASCII control codes used in terminal operations
-enum ASCIICode {
+pub(super) enum ASCIICode {
+    CtrlA = 0x01,
+    CtrlC = 0x03,
+    CtrlD = 0x04,
+    CtrlE = 0x05,
+    CtrlG = 0x07,
     Backspace = 0x08,
+    Tab = 0x09,
     Newline = 0x0A,
+    CtrlK = 0x0B,
+    CtrlL = 0x0C,
     CarriageReturn = 0x0D,
+    CtrlR = 0x12,
+    CtrlU = 0x15,
+    CtrlW = 0x17,
+    CtrlY = 0x19,
+    CtrlUnderscore = 0x1F,
     Escape = 0x1B,
     Space = 0x20,
+    Semicolon = 0x3B,
+    SaveCursor = 0x37,
+    RestoreCursor = 0x38,
+    ArrowUp = 0x41,
+    ArrowDown = 0x42,
     ArrowRight = 0x43,
     ArrowLeft = 0x44,
+    CursorPosition = 0x48,
+    ScrollRegion = 0x72,
     LeftBracket = 0x5B,
     Delete = 0x7F,
 }
 
 /// ANSI terminal control sequences
-enum ASCIIControl {
+pub(super) enum ASCIIControl {
     /// Clears from cursor to end of line
     ClearToEndOfLine,
 
+    /// Clears from cursor to end of screen
+    ClearToEndOfScreen,
+
     /// Clears entire screen
     ClearScreen,
 
@@ -38,120 +136,386 @@ enum ASCIIControl {
 
     /// Resets all text formatting
     ClearFormatting,
+
+    /// Asks the terminal to mark pasted text with `ESC [ 200 ~` and `ESC [ 201 ~`
+    EnableBracketedPaste,
+
+    /// Moves the cursor as far down and right as the terminal allows, i.e. to the last
+    /// column of the last row
+    MoveCursorToBottomRight,
+
+    /// Asks the terminal to report the cursor position as `ESC [ row ; column R`
+    QueryCursorPosition,
+
+    /// Hides the cursor
+    HideCursor,
+
+    /// Shows the cursor again
+    ShowCursor,
+
+    /// Lets the whole screen scroll again, undoing a scroll region
+    ResetScrollRegion,
 }
 
 impl ASCIIControl {
     /// Converts the control sequence to its byte representation
-    fn as_bytes(&self) -> &[u8] {
+    pub(super) fn as_bytes(&self) -> &[u8] {
         match self {
             ASCIIControl::ClearToEndOfLine => b"0K",
+            ASCIIControl::ClearToEndOfScreen => b"0J",
             ASCIIControl::ClearScreen => b"2J",
             ASCIIControl::MoveCursorToTop => b"H",
             ASCIIControl::ClearFormatting => b"0m",
+            ASCIIControl::EnableBracketedPaste => b"?2004h",
+            ASCIIControl::MoveCursorToBottomRight => b"999;999H",
+            ASCIIControl::QueryCursorPosition => b"6n",
+            ASCIIControl::HideCursor => b"?25l",
+            ASCIIControl::ShowCursor => b"?25h",
+            ASCIIControl::ResetScrollRegion => b"r",
         }
     }
 }
 
-/// Represents the state of escape sequence processing
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum EscapeState {
-    /// No escape sequence in progress
-    NotReceived,
-    /// ESC character received
-    Received,
-    /// ESC [ sequence received
-    BracketReceived,
-}
-
 /// Main terminal editor structure for handling terminal input/output
-pub struct Terminal {
-    /// Current cursor position in the line
+pub struct Terminal<IO: ByteIo> {
+    /// Current cursor position in the line, as a byte index on a character boundary
     cursor: usize,
 
-    /// Current state of escape sequence processing
-    escape_state: EscapeState,
+    /// Parser for incoming escape sequences
+    pub(super) escape_parser: EscapeParser,
 
-    /// UART peripheral instance
-    uart: Uart,
+    /// Decoder assembling multi-byte UTF-8 input
+    utf8_decoder: Utf8Decoder,
+
+    /// Transport used for input and output
+    pub(super) io: IO,
 
     /// Current line buffer
-    current_line: heapless::Vec<u8, MAX_LINE_LENGTH>,
+    pub(super) current_line: heapless::Vec<u8, MAX_LINE_LENGTH>,
+
+    /// Previously submitted lines
+    pub(super) history: History,
+
+    /// State of the reverse incremental history search, while one is active
+    search: Option<ReverseSearch>,
+
+    /// Snapshots of the current line for undo and redo
+    pub(super) journal: UndoJournal,
+
+    /// Text removed by kill commands, available for yanking
+    pub(super) kill_ring: KillRing,
+
+    /// Editing command handled for the previous key
+    last_action: LastAction,
+
+    /// State of vi-style editing, `None` while emacs-style editing is used
+    vi: Option<ViState>,
+
+    /// Uptime in microseconds at which an ESC without a following byte was first seen
+    escape_since: Option<u64>,
+
+    /// Text received since the start of a bracketed paste, while one is in progress
+    paste: Option<Paste>,
+
+    /// Style used for the prompt and banner
+    prompt_style: Style,
+
+    /// Characters that separate words for word motion and deletion
+    word_separators: heapless::Vec<u8, MAX_WORD_SEPARATORS>,
+
+    /// Number of columns of the terminal window
+    pub(super) width: usize,
+
+    /// Row of the terminal cursor, counted from the row the prompt starts on
+    screen_row: usize,
+
+    /// Number of rows of the terminal window, once known; command output is only paged
+    /// while it is
+    pub(super) height: Option<usize>,
 
-    /// Color used for the prompt
-    prompt_color: TerminalTextColor,
+    /// Whether a cursor position report answering a size query is expected
+    size_query_pending: bool,
+
+    /// Uptime at which the pending size query was sent, if the uptime is known
+    size_query_sent_us: Option<u64>,
+
+    /// Row count of the output of the running command, while it is paged
+    pub(super) pager: Option<Pager>,
+
+    /// Whether paged output waits at the `--More--` prompt for a key
+    pub(super) output_paused: bool,
+
+    /// Command output written while paged output waits, shown once the user goes on
+    pub(super) paged_output: heapless::Vec<u8, PAGED_OUTPUT_SIZE>,
+
+    /// Step writing the rest of the running command's output and the indices it is
+    /// still to be called with, see [`Self::produce_output`]
+    pub(super) output_steps: Option<(OutputStep<IO>, Range<usize>)>,
+
+    /// Input received after the key that finished a form or paged output, not yet
+    /// processed
+    pub(super) held_input: heapless::Vec<u8, RX_BUFFER_SIZE>,
+
+    /// Whether styling escape sequences are sent
+    styling: bool,
+
+    /// Whether no escape sequences are sent at all, for dumb terminals and log captures
+    pub(super) plain: bool,
+
+    /// Line requested by a command handler, delivered instead of running a command
+    pub(super) line_request: Option<LineRequest<IO>>,
+
+    /// Full-screen view shown instead of the prompt
+    pub(super) view: Option<View<IO>>,
+
+    /// Status bar pinned to the top or bottom row, once enabled
+    pub(super) status_bar: Option<StatusBar<IO>>,
 
     /// Banner text displayed at startup
     cli_banner: &'static [u8],
 
-    /// Prompt text displayed at the start of each line
+    /// Default prompt template, restored by [`Self::reset_prompt_template`]
     cli_prompt: &'static [u8],
+
+    /// Template expanded into the prompt displayed at the start of each line
+    prompt_template: PromptTemplate,
+
+    /// Prompt as last printed, which the cursor arithmetic measures
+    displayed_prompt: ExpandedPrompt,
+
+    /// Text shown by the `{context}` prompt placeholder
+    pub(super) context: Context,
+
+    /// Exit status of the last command, shown by the `{status}` prompt placeholder
+    exit_status: u8,
+
+    /// Commands that can be invoked from the prompt
+    commands: &'static [Command<IO>],
+
+    /// Line ending expected from the remote terminal
+    input_line_ending: InputLineEnding,
+
+    /// Newline sequence written at the end of each output line
+    pub(super) output_newline: OutputNewline,
+
+    /// Byte that completes the line ending just received, if any
+    ///
+    /// In `Auto` mode this is the second half of a CR LF or LF CR pair that must be
+    /// swallowed; in `CrLf` mode it is the LF that submits the line after a CR.
+    pub(super) pending_line_ending: Option<u8>,
+}
+
+/// State of a reverse incremental history search (Ctrl-R)
+struct ReverseSearch {
+    /// Text typed since the search started
+    query: Line,
+
+    /// Steps back of the matching history entry, or `None` if nothing matches
+    found: Option<usize>,
+}
+
+/// Editing command handled for the previous key
+///
+/// Consecutive typed characters are undone together, and Alt-Y only applies directly
+/// after a yank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LastAction {
+    /// Any command without follow-up behaviour
+    Other,
+
+    /// A printable character was typed
+    Typing,
+
+    /// Killed text was yanked into `start..end` of the line buffer
+    Yank {
+        start: usize,
+        end: usize,
+
+        /// Kill ring entry that was yanked, counted back from the most recent
+        steps_back: usize,
+    },
+}
+
+/// State of a bracketed paste in progress
+struct Paste {
+    /// Pasted text of the current line not yet inserted into the line buffer
+    text: Line,
+
+    /// Number of pasted bytes that did not fit on their line
+    truncated: usize,
 }
 
-/// Available colors for terminal text
-#[derive(Debug)]
+/// How typed characters are displayed while a line is edited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(unused)]
-pub enum TerminalTextColor {
-    Red,
-    Green,
-    Blue,
+pub enum Echo {
+    /// Characters are shown as typed
+    Normal,
+
+    /// Each character is shown as `*`, for secrets whose length may be seen
+    Masked,
+
+    /// Nothing is shown, not even the cursor moving
+    Hidden,
+}
+
+/// Key bindings used to edit the line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditMode {
+    /// Modeless editing with emacs-style control keys
+    Emacs,
+
+    /// Modal editing with vi-style commands, see [`crate::peripherals::uart::vi`]
+    Vi,
+}
+
+/// Line ending sent by the remote terminal when Enter is pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputLineEnding {
+    /// A carriage return submits the line, line feeds are ignored
+    Cr,
+
+    /// A line feed submits the line, carriage returns are ignored
+    Lf,
+
+    /// Only a carriage return directly followed by a line feed submits the line
+    CrLf,
+
+    /// Either byte submits the line, and the other half of a CR LF or LF CR pair is ignored
+    Auto,
+}
+
+/// Newline sequence written at the end of each output line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputNewline {
+    /// Carriage return followed by line feed
+    CrLf,
+
+    /// Line feed only, for hosts that translate it themselves
+    Lf,
+
+    /// Carriage return only
+    Cr,
 }
 
-impl TerminalTextColor {
-    /// Converts the color to its ANSI escape sequence
-    fn as_bytes(&self) -> &[u8] {
+impl OutputNewline {
+    /// Converts the newline to the bytes sent to the terminal
+    pub(super) fn as_bytes(&self) -> &'static [u8] {
         match self {
-            TerminalTextColor::Red => b"31m",
-            TerminalTextColor::Green => b"32m",
-            TerminalTextColor::Blue => b"34m",
+            OutputNewline::CrLf => b"\r\n",
+            OutputNewline::Lf => b"\n",
+            OutputNewline::Cr => b"\r",
         }
     }
 }
 
-impl Terminal {
+impl<IO: ByteIo> Terminal<IO> {
     /// Creates a new Terminal instance
     ///
     /// # Arguments
     ///
-    /// * `uart_peripheral` - The UART0 peripheral to use
-    /// * `uart_clock_freq` - The UART peripheral clock frequency in Hz
-    /// * `resets` - Mutable reference to the RESETS peripheral
-    /// * `prompt_color` - Color to use for the prompt
+    /// * `io` - Transport to read input from and write output to
+    /// * `prompt_style` - Style to use for the prompt and banner
     /// * `cli_banner` - Banner text displayed at startup
-    /// * `cli_prompt` - Prompt text displayed before each line
+    /// * `cli_prompt` - Prompt template displayed before each line, see [`Self::set_prompt_template`]
+    /// * `commands` - Command registry used to dispatch submitted lines
     ///
     /// # Returns
     ///
     /// A new Terminal instance with initialized terminal
     pub fn new(
-        uart_peripheral: UART0,
-        uart_clock_freq: u32,
-        resets: &mut RESETS,
-        prompt_color: TerminalTextColor,
+        io: IO,
+        prompt_style: Style,
         cli_banner: &'static [u8],
         cli_prompt: &'static [u8],
+        commands: &'static [Command<IO>],
     ) -> Self {
-        let uart = Uart::new(uart_peripheral, uart_clock_freq, resets);
         let current_line: heapless::Vec<u8, MAX_LINE_LENGTH> = heapless::Vec::new();
         let mut editor = Terminal {
             cursor: 0,
-            escape_state: NotReceived,
-            uart,
+            escape_parser: EscapeParser::new(),
+            utf8_decoder: Utf8Decoder::new(),
+            io,
             current_line,
-            prompt_color,
+            history: History::new(),
+            search: None,
+            journal: UndoJournal::new(),
+            kill_ring: KillRing::new(),
+            last_action: LastAction::Other,
+            vi: None,
+            escape_since: None,
+            paste: None,
+            prompt_style,
+            word_separators: heapless::Vec::from_slice(DEFAULT_WORD_SEPARATORS).unwrap_or_default(),
+            width: DEFAULT_TERMINAL_WIDTH,
+            screen_row: 0,
+            height: None,
+            size_query_pending: false,
+            size_query_sent_us: None,
+            pager: None,
+            output_paused: false,
+            paged_output: heapless::Vec::new(),
+            output_steps: None,
+            held_input: heapless::Vec::new(),
+            styling: true,
+            plain: false,
+            line_request: None,
+            view: None,
+            status_bar: None,
             cli_banner,
             cli_prompt,
+            prompt_template: PromptTemplate::new(cli_prompt).unwrap_or_default(),
+            displayed_prompt: ExpandedPrompt::default(),
+            context: Context::new(),
+            exit_status: 0,
+            commands,
+            input_line_ending: InputLineEnding::Auto,
+            output_newline: OutputNewline::CrLf,
+            pending_line_ending: None,
         };
 
+        editor.print_control_sequence(ASCIIControl::EnableBracketedPaste.as_bytes());
         editor.clear_screen();
+        editor.request_size();
         editor.print_banner();
         editor.print_prompt();
         editor
     }
 
-    /// Prints the prompt at the beginning of the line
-    fn print_prompt(&mut self) {
-        let prompt = self.cli_prompt;
-        self.print(prompt, true);
+    /// Expands the prompt template and prints the prompt at the beginning of the line
+    ///
+    /// A requested line shows the prompt given with the request instead, as is. A failed
+    /// exit status is highlighted as an error. In vi mode, the prompt is preceded by the
+    /// current editing mode.
+    pub(super) fn print_prompt(&mut self) {
+        let mut prompt = match &self.line_request {
+            Some(request) => ExpandedPrompt::verbatim(request.prompt),
+            None => self.prompt_template.expand(&PromptFields {
+                uptime_us: uptime_us(),
+                exit_status: self.exit_status,
+                uart_index: self.io.uart_index(),
+                context: &self.context,
+            }),
+        };
+        if let Some(vi) = &self.vi {
+            prompt = prompt.prefixed(vi.mode().indicator());
+        }
+
+        let text = prompt.text.as_bytes();
+        let alert = prompt.alert.clone();
+        for (range, style) in [
+            (0..alert.start, self.prompt_style),
+            (alert.clone(), Style::ERROR),
+            (alert.end..text.len(), self.prompt_style),
+        ] {
+            if !range.is_empty() {
+                self.print_styled(&text[range], style);
+            }
+        }
+
+        self.displayed_prompt = prompt;
+        // The prompt always starts on a fresh row
+        self.screen_row = 0;
     }
 
     /// Prints the CLI banner at startup
@@ -161,35 +525,116 @@ impl Terminal {
     }
 
     /// Prints the escape sequence ESC + [
-    fn print_escape_sequence(&mut self) {
-        self.uart.putc(ASCIICode::Escape as u8);
-        self.uart.putc(ASCIICode::LeftBracket as u8);
+    pub(super) fn print_escape_sequence(&mut self) {
+        self.io.putc(ASCIICode::Escape as u8);
+        self.io.putc(ASCIICode::LeftBracket as u8);
     }
 
     /// Prints a control sequence ESC + [ + control_sequence
-    fn print_control_sequence(&mut self, control_sequence: &[u8]) {
+    pub(super) fn print_control_sequence(&mut self, control_sequence: &[u8]) {
         self.print_escape_sequence();
-        self.uart.print(control_sequence);
+        self.io.print(control_sequence);
     }
 
     /// Clears the screen
+    ///
+    /// An active status bar is kept: its scroll region is set again and the bar redrawn,
+    /// leaving the cursor at the start of the first row output scrolls in. Plain output
+    /// cannot clear anything, so only the line being edited is dropped.
     pub fn clear_screen(&mut self) {
+        if self.plain {
+            self.cursor = 0;
+            self.current_line.clear();
+            self.screen_row = 0;
+            return;
+        }
+
         self.print_control_sequence(ASCIIControl::ClearScreen.as_bytes());
-        self.print_control_sequence(ASCIIControl::MoveCursorToTop.as_bytes());
+        match self.status_bar_layout() {
+            Some((position, rows)) => {
+                let (top, bottom) = position.scroll_region(rows);
+                self.print_scroll_region(top, bottom);
+                if let Some(bar) = &mut self.status_bar {
+                    bar.shown.clear();
+                }
+                self.draw_status_bar(true);
+                self.print_cursor_position(position.first_output_row(), 0);
+            }
+            None => {
+                self.print_control_sequence(ASCIIControl::MoveCursorToTop.as_bytes());
+            }
+        }
         self.cursor = 0;
         self.current_line.clear();
+        self.screen_row = 0;
     }
 
-    /// Applies the color passed at initialisation to the prompt text
-    fn apply_prompt_color(&mut self) {
-        self.print_escape_sequence();
-        let prompt_color = self.prompt_color.as_bytes();
-        self.uart.print(prompt_color);
+    /// Applies a style to the text that follows, unless styling is switched off
+    pub(super) fn apply_style(&mut self, style: Style) {
+        if self.styling && !self.plain {
+            self.io.print(&style.to_sgr());
+        }
+    }
+
+    /// Clears all text formatting, unless styling is switched off
+    pub(super) fn clear_formatting(&mut self) {
+        if self.styling && !self.plain {
+            self.print_control_sequence(ASCIIControl::ClearFormatting.as_bytes());
+        }
+    }
+
+    /// Enables or disables styling escape sequences for all output
+    ///
+    /// With styling disabled, text printed through this terminal carries no colour or
+    /// attribute codes. Cursor movement is unaffected; see [`Self::set_plain`] to send no
+    /// escape sequences at all.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to send styling escape sequences
+    pub fn set_styling(&mut self, enabled: bool) {
+        self.styling = enabled;
+    }
+
+    /// Returns whether styling escape sequences are sent
+    pub fn styling(&self) -> bool {
+        self.styling
+    }
+
+    /// Switches plain output, which sends no escape sequences at all, on or off
+    ///
+    /// Plain output suits dumb terminals and log captures. Besides styling, it leaves out
+    /// cursor movement and clearing: the line being edited is updated with backspaces
+    /// and by printing text again, and is redrawn on a new row where it would otherwise
+    /// be redrawn in place. Lines are not wrapped, and the screen cannot be cleared.
+    /// Size queries, full-screen views and the status bar are unavailable; an active
+    /// status bar is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `plain` - Whether to leave out all escape sequences
+    pub fn set_plain(&mut self, plain: bool) {
+        if plain {
+            self.hide_status_bar();
+            self.size_query_pending = false;
+        }
+        self.plain = plain;
     }
 
-    /// Clears all text formatting
-    fn clear_formatting(&mut self) {
-        self.print_control_sequence(ASCIIControl::ClearFormatting.as_bytes());
+    /// Returns whether plain output without escape sequences is used
+    pub fn plain(&self) -> bool {
+        self.plain
+    }
+
+    /// Reports that a feature needing escape sequences is unavailable in plain output,
+    /// marking the running command as failed
+    ///
+    /// # Arguments
+    ///
+    /// * `feature` - Name of the feature, e.g. `"full-screen views"`
+    pub(super) fn report_plain(&mut self, feature: &str) {
+        cli_println!(self, warning, "{} are unavailable in plain output", feature);
+        self.exit_status = EXIT_FAILURE;
     }
 
     /// Prints text to the terminal
@@ -197,179 +642,2755 @@ impl Terminal {
     /// # Arguments
     ///
     /// * `s` - Text to print
-    /// * `apply_prompt_color` - Whether to apply the configured prompt color to the text
+    /// * `apply_prompt_color` - Whether to apply the configured prompt style to the text
     pub fn print(&mut self, s: &[u8], apply_prompt_color: bool) {
-        if apply_prompt_color {
-            self.apply_prompt_color();
-        }
+        let style = if apply_prompt_color {
+            self.prompt_style
+        } else {
+            Style::new()
+        };
+        self.print_styled(s, style);
+    }
+
+    /// Prints text to the terminal in the given style
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Text to print
+    /// * `style` - Colours and attributes to apply, e.g. [`Style::ERROR`]
+    pub fn print_styled(&mut self, s: &[u8], style: Style) {
+        self.apply_style(style);
+        self.output(s);
+        self.clear_formatting();
+    }
 
-        self.uart.print(s);
+    /// Writes formatted text in the given style
+    ///
+    /// Used by the [`cli_print!`](crate::peripherals::uart::print::cli_print) macros;
+    /// line feeds in the text are sent as the configured output newline.
+    ///
+    /// # Arguments
+    ///
+    /// * `style` - Colours and attributes to apply, e.g. [`Style::ERROR`]
+    /// * `args` - Formatted text, as produced by `format_args!`
+    pub fn write_styled(&mut self, style: Style, args: fmt::Arguments) {
+        self.apply_style(style);
+        let _ = fmt::Write::write_fmt(self, args);
         self.clear_formatting();
     }
 
-    /// Processes input from the UART
+    /// Prints text to the terminal in the given style followed by the configured output
+    /// newline
     ///
-    /// This method should be called regularly to handle incoming characters
-    /// and update the terminal state.
-    pub fn process_input(&mut self) {
-        let mut bytes = self.uart.get_input();
-        self.process_bytes(&mut bytes);
+    /// # Arguments
+    ///
+    /// * `s` - Text to print
+    /// * `style` - Colours and attributes to apply, e.g. [`Style::ERROR`]
+    pub fn print_styled_line(&mut self, s: &[u8], style: Style) {
+        self.print_styled(s, style);
+        self.print_newline();
     }
 
-    /// Moves the cursor one character to the right
-    fn move_cursor_left(&mut self) {
-        match self.cursor {
-            x if x > 0 => {
-                self.uart.putc(ASCIICode::Escape as u8);
-                self.uart.putc(ASCIICode::LeftBracket as u8);
-                self.uart.putc(ASCIICode::ArrowLeft as u8);
-                self.cursor -= 1;
-            }
+    /// Prints text to the terminal followed by the configured output newline
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Text to print
+    pub fn print_line(&mut self, s: &[u8]) {
+        self.print(s, false);
+        self.print_newline();
+    }
 
-            _ => {}
-        }
+    /// Moves to the start of the next line using the configured output newline
+    fn print_newline(&mut self) {
+        let newline = self.output_newline.as_bytes();
+        self.output(newline);
     }
 
-    /// Moves the cursor one character to the right
-    fn move_cursor_right(&mut self) {
-        match self.cursor {
-            x if x < self.current_line.len() => {
-                self.uart.putc(ASCIICode::Escape as u8);
-                self.uart.putc(ASCIICode::LeftBracket as u8);
-                self.uart.putc(ASCIICode::ArrowRight as u8);
-                self.cursor += 1;
-            }
+    /// Sets the line ending expected from the remote terminal
+    ///
+    /// # Arguments
+    ///
+    /// * `line_ending` - Byte sequence that submits a line
+    pub fn set_input_line_ending(&mut self, line_ending: InputLineEnding) {
+        self.input_line_ending = line_ending;
+        self.pending_line_ending = None;
+    }
 
-            _ => {}
-        }
+    /// Returns the line ending expected from the remote terminal
+    pub fn input_line_ending(&self) -> InputLineEnding {
+        self.input_line_ending
     }
 
-    /// Erases all characters in the current line and moves the cursor to the beginning of the line
-    fn clear_line(&mut self) {
-        self.print_control_sequence(ASCIIControl::ClearToEndOfLine.as_bytes());
+    /// Sets the newline sequence written at the end of each output line
+    ///
+    /// # Arguments
+    ///
+    /// * `newline` - Byte sequence to write
+    pub fn set_output_newline(&mut self, newline: OutputNewline) {
+        self.output_newline = newline;
+    }
 
-        // The carriage return after the ClearToEndOfLine sequence resets the cursor position
-        self.uart.putc(ASCIICode::CarriageReturn as u8);
-        self.cursor = 0;
+    /// Returns the newline sequence written at the end of each output line
+    pub fn output_newline(&self) -> OutputNewline {
+        self.output_newline
+    }
 
-        // Print the prompt again
-        self.print_prompt();
+    /// Sets the number of columns of the terminal window
+    ///
+    /// Lines longer than a row are wrapped onto the rows below.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - Width of the terminal window
+    pub fn set_width(&mut self, columns: usize) {
+        self.width = columns.max(1);
+        self.size_query_pending = false;
+    }
+
+    /// Returns the number of columns of the terminal window
+    pub fn width(&self) -> usize {
+        self.width
     }
 
-    /// Rewrites characters from the current cursor position to the end of the line
-    fn rewrite_line(&mut self) {
-        let original_cursor = self.cursor;
+    /// Sets the number of rows of the terminal window
+    ///
+    /// Command output longer than a screen is paged.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - Height of the terminal window, or `None` to stop paging
+    pub fn set_height(&mut self, rows: Option<usize>) {
+        self.height = rows.map(|rows| rows.max(2));
+        self.size_query_pending = false;
+    }
 
-        // Clear the line
-        self.clear_line();
+    /// Returns the number of rows of the terminal window, if known
+    pub fn height(&self) -> Option<usize> {
+        self.height
+    }
 
-        // Rewrite the contents
-        for &byte in self.current_line.iter().skip(self.cursor) {
-            self.cursor += 1;
-            self.uart.putc(byte);
+    /// Asks the terminal for the size of its window
+    ///
+    /// The cursor is saved, moved to the bottom-right corner and its position requested
+    /// with `ESC [ 6 n`, then restored. The reply is picked up by
+    /// [`Self::process_input`]; without one, the current size is kept. A report arriving
+    /// after [`SIZE_QUERY_TIMEOUT_US`] is handled as the key it also encodes, e.g.
+    /// `ESC [ 1 ; 2 R` as Shift-F3. Plain output sends no query.
+    pub fn request_size(&mut self) {
+        if self.plain {
+            return;
         }
 
-        self.cursor = self.current_line.len();
-        while self.cursor > original_cursor {
-            self.move_cursor_left();
+        self.io.putc(ASCIICode::Escape as u8);
+        self.io.putc(ASCIICode::SaveCursor as u8);
+        self.print_control_sequence(ASCIIControl::MoveCursorToBottomRight.as_bytes());
+        self.print_control_sequence(ASCIIControl::QueryCursorPosition.as_bytes());
+        self.io.putc(ASCIICode::Escape as u8);
+        self.io.putc(ASCIICode::RestoreCursor as u8);
+        self.size_query_pending = true;
+        self.size_query_sent_us = uptime_us();
+    }
+
+    /// Sets the template the prompt is expanded from
+    ///
+    /// The template is expanded every time the prompt is printed. It may contain the
+    /// placeholders `{uptime}`, `{status}`, `{uart}` and `{context}`, see
+    /// [`crate::peripherals::uart::prompt`].
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Prompt text with placeholders
+    ///
+    /// # Returns
+    ///
+    /// `false` if the template is longer than [`PROMPT_TEMPLATE_LENGTH`] bytes, in which
+    /// case the current one is kept
+    ///
+    /// [`PROMPT_TEMPLATE_LENGTH`]: crate::constants::PROMPT_TEMPLATE_LENGTH
+    pub fn set_prompt_template(&mut self, template: &str) -> bool {
+        match PromptTemplate::new(template.as_bytes()) {
+            Some(template) => {
+                self.prompt_template = template;
+                true
+            }
+            None => false,
         }
     }
 
-    /// Moves the cursor to the beginning of the next line
-    fn newline(&mut self) {
-        self.uart.putc(ASCIICode::CarriageReturn as u8);
-        self.uart.putc(ASCIICode::Newline as u8);
-        self.print_prompt();
-        self.cursor = 0;
+    /// Restores the prompt template the terminal was created with
+    pub fn reset_prompt_template(&mut self) {
+        self.prompt_template = PromptTemplate::new(self.cli_prompt).unwrap_or_default();
+    }
 
-        // @todo: Trigger command processing
+    /// Returns the template the prompt is expanded from
+    pub fn prompt_template(&self) -> &str {
+        self.prompt_template.as_str()
     }
 
-    /// Deletes the previous character and moves the cursor left
-    fn backspace(&mut self) {
-        if self.cursor > 0 {
-            self.current_line.remove(self.cursor - 1);
-            self.move_cursor_left();
-            self.rewrite_line();
+    /// Sets the text shown by the `{context}` prompt placeholder
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - Text identifying the board or its state; empty to clear it
+    ///
+    /// # Returns
+    ///
+    /// `false` if the text is longer than [`PROMPT_CONTEXT_LENGTH`] bytes, in which case
+    /// the current one is kept
+    ///
+    /// [`PROMPT_CONTEXT_LENGTH`]: crate::constants::PROMPT_CONTEXT_LENGTH
+    pub fn set_context(&mut self, context: &str) -> bool {
+        match Context::try_from(context) {
+            Ok(context) => {
+                self.context = context;
+                true
+            }
+            Err(_) => false,
         }
     }
 
-    /// Inserts a space at the current character position
-    fn space(&mut self) {
-        if self.current_line.len() < MAX_LINE_LENGTH {
-            self.current_line
-                .insert(self.cursor, ASCIICode::Space as u8)
-                .unwrap();
-            self.move_cursor_right();
-            self.rewrite_line();
-        }
+    /// Sets the exit status of the running command, shown by the `{status}` prompt
+    /// placeholder
+    ///
+    /// Commands that do not set one succeed with status 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - 0 for success, e.g. [`EXIT_FAILURE`] or [`EXIT_USAGE`] otherwise
+    pub fn set_exit_status(&mut self, status: u8) {
+        self.exit_status = status;
     }
 
-    /// Inserts a character at the current cursor position
-    fn insert_character(&mut self, data: u8) {
-        if self.current_line.len() < MAX_LINE_LENGTH {
-            self.current_line.insert(self.cursor, data).unwrap();
-            self.uart.putc(data);
-            self.cursor += 1;
-        }
+    /// Returns the receive errors counted by the transport, if it counts them
+    pub fn uart_errors(&self) -> Option<UartErrors> {
+        self.io.uart_errors()
     }
 
-    /// Processes a buffer of input bytes
+    /// Returns the speed and frame format of the UART the terminal runs on, if any
+    pub fn uart_settings(&self) -> Option<UartSettings> {
+        self.io.uart_settings()
+    }
+
+    /// Changes the speed and frame format of the UART the terminal runs on
+    ///
+    /// The remote terminal has to switch to the same settings to read further output.
     ///
     /// # Arguments
     ///
-    /// * `buffer` - Buffer containing input bytes to process
-    pub fn process_bytes(&mut self, buffer: &mut heapless::Vec<u8, MAX_LINE_LENGTH>) {
-        for &data in buffer.iter() {
-            match self.escape_state {
-                NotReceived => match data {
-                    x if x == ASCIICode::Escape as u8 => {
-                        self.escape_state = Received; // '\x1b' (ESC)
-                    }
+    /// * `settings` - Settings to switch to
+    ///
+    /// # Returns
+    ///
+    /// `false` if the terminal does not run on a UART or the baud rate is not supported
+    pub fn set_uart_settings(&mut self, settings: UartSettings) -> bool {
+        self.io.set_uart_settings(settings)
+    }
 
-                    x if x == ASCIICode::CarriageReturn as u8 || x == ASCIICode::Newline as u8 => {
-                        self.newline();
-                        self.current_line.clear();
-                    }
+    /// Stops waiting for the answer to a size query not answered within
+    /// [`SIZE_QUERY_TIMEOUT_US`], disabling a status bar that waited for it
+    ///
+    /// Without a timer, the query expires if the answer is not among the input received
+    /// up to the next update. A status bar also stops waiting once the query was
+    /// dropped because the size was set by hand.
+    fn expire_size_query(&mut self) {
+        let expired = match (uptime_us(), self.size_query_sent_us) {
+            (Some(now), Some(sent)) => now.saturating_sub(sent) >= SIZE_QUERY_TIMEOUT_US,
+            _ => true,
+        };
+        if self.size_query_pending && !expired {
+            return;
+        }
 
-                    x if x == ASCIICode::Backspace as u8 || x == ASCIICode::Delete as u8 => {
-                        self.backspace();
-                    }
+        self.size_query_pending = false;
+        if self
+            .status_bar
+            .as_ref()
+            .is_some_and(|bar| bar.rows.is_none())
+        {
+            self.status_bar = None;
+            self.warn_above(format_args!(
+                "status bar: no answer from the terminal, status bar disabled"
+            ));
+        }
+    }
 
-                    x if x == ASCIICode::Space as u8 => {
-                        self.space();
-                    }
+    /// Sets the characters that separate words for word motion and deletion
+    ///
+    /// # Arguments
+    ///
+    /// * `separators` - ASCII characters ending a word, e.g. [`DEFAULT_WORD_SEPARATORS`]
+    ///
+    /// # Returns
+    ///
+    /// `false` if there are more than [`MAX_WORD_SEPARATORS`] characters or any of them
+    /// is not ASCII, in which case the current ones are kept
+    pub fn set_word_separators(&mut self, separators: &[u8]) -> bool {
+        if !separators.is_ascii() {
+            return false;
+        }
+        match heapless::Vec::from_slice(separators) {
+            Ok(separators) => {
+                self.word_separators = separators;
+                true
+            }
+            Err(_) => false,
+        }
+    }
 
-                    0x21..=0x7E => {
-                        self.insert_character(data);
-                    }
+    /// Returns the characters that separate words for word motion and deletion
+    pub fn word_separators(&self) -> &[u8] {
+        &self.word_separators
+    }
 
-                    _ => {}
-                },
+    /// Selects emacs-style or vi-style line editing
+    ///
+    /// Vi mode starts every line in insert mode. The new mode applies from the next
+    /// prompt on.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - Key bindings to use
+    pub fn set_edit_mode(&mut self, mode: EditMode) {
+        if mode != self.edit_mode() {
+            self.vi = match mode {
+                EditMode::Emacs => None,
+                EditMode::Vi => Some(ViState::new()),
+            };
+        }
+    }
 
-                Received => match data {
-                    x if x == ASCIICode::LeftBracket as u8 => {
-                        self.escape_state = BracketReceived;
-                    }
-                    _ => {
-                        self.escape_state = NotReceived;
-                    }
-                },
-
-                BracketReceived => {
-                    match data {
-                        x if x == ASCIICode::ArrowLeft as u8 => {
-                            self.move_cursor_left();
-                        }
-                        x if x == ASCIICode::ArrowRight as u8 => {
-                            self.move_cursor_right();
-                        }
-
-                        _ => {}
-                    }
-                    self.escape_state = NotReceived;
-                }
-            }
+    /// Returns the key bindings used to edit the line
+    pub fn edit_mode(&self) -> EditMode {
+        if self.vi.is_some() {
+            EditMode::Vi
+        } else {
+            EditMode::Emacs
         }
     }
+
+    /// Returns the command registry this terminal dispatches to
+    pub fn commands(&self) -> &'static [Command<IO>] {
+        self.commands
+    }
+
+    /// Processes input from the transport
+    ///
+    /// This method should be called regularly to handle incoming characters
+    /// and update the terminal state.
+    pub fn process_input(&mut self) {
+        if self.view.is_some() {
+            self.process_view_input();
+            return;
+        }
+        if self.output_paused {
+            // Log messages stay queued until the prompt is back
+            loop {
+                self.process_paged_input();
+                if self.output_paused {
+                    break;
+                }
+                self.continue_output();
+                if !self.output_paused || self.held_input.is_empty() {
+                    break;
+                }
+            }
+            self.refresh_status_bar();
+            return;
+        }
+
+        let size_query_pending = self.size_query_pending
+            || self
+                .status_bar
+                .as_ref()
+                .is_some_and(|bar| bar.rows.is_none());
+
+        let held = core::mem::take(&mut self.held_input);
+        self.process_bytes(&held);
+
+        let log = self.io.get_log();
+        if !log.is_empty() {
+            self.print_above(&log);
+        }
+
+        let bytes = self.io.get_input();
+        let dropped = self.io.take_dropped_input();
+        if dropped > 0 {
+            self.warn_above(format_args!(
+                "input overflow: {dropped} received bytes were lost"
+            ));
+        }
+
+        self.process_bytes(&bytes);
+        self.expire_escape();
+        if size_query_pending {
+            self.expire_size_query();
+        }
+        self.refresh_status_bar();
+    }
+
+    /// Takes an ESC that no byte followed within [`ESCAPE_TIMEOUT_US`] as the Esc key
+    ///
+    /// This only matters in vi insert mode, where Esc switches to normal mode. Without a
+    /// timer, the ESC is taken as the Esc key once the input received with it is processed.
+    fn expire_escape(&mut self) {
+        let inserting = self
+            .vi
+            .as_ref()
+            .is_some_and(|vi| vi.mode() == ViMode::Insert);
+        if !inserting {
+            self.escape_since = None;
+            return;
+        }
+        if self.take_expired_escape() {
+            self.vi_escape();
+        }
+    }
+
+    /// Drops an ESC that no byte followed within [`ESCAPE_TIMEOUT_US`]
+    ///
+    /// # Returns
+    ///
+    /// `true` if such an ESC was pending, to be taken as the Esc key
+    pub(super) fn take_expired_escape(&mut self) -> bool {
+        if !self.escape_parser.is_escape_pending() {
+            self.escape_since = None;
+            return false;
+        }
+
+        let now = uptime_us();
+        let since = *self.escape_since.get_or_insert(now.unwrap_or(0));
+        if now.is_none_or(|now| now.saturating_sub(since) >= ESCAPE_TIMEOUT_US) {
+            self.escape_parser.reset();
+            self.escape_since = None;
+            return true;
+        }
+        false
+    }
+
+    /// Prints a message above the prompt and redraws the line being edited
+    ///
+    /// The current row is cleared, the message printed on its own lines, and the prompt,
+    /// line buffer and cursor position restored below it.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - Text to print; line feeds split it over several lines
+    pub fn print_above(&mut self, message: &[u8]) {
+        self.clear_edit_area();
+
+        let message = message.strip_suffix(b"\n").unwrap_or(message);
+        for line in message.split(|&byte| byte == ASCIICode::Newline as u8) {
+            self.print_line(line);
+        }
+
+        self.restore_edit_area();
+    }
+
+    /// Prints a formatted warning above the prompt and redraws the line being edited
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - Formatted message, as produced by `format_args!`
+    fn warn_above(&mut self, args: fmt::Arguments) {
+        self.clear_edit_area();
+        self.write_styled(Style::WARNING, args);
+        self.print_newline();
+        self.restore_edit_area();
+    }
+
+    /// Clears the rows holding the prompt and line being edited, leaving the cursor at
+    /// the start of the first one
+    ///
+    /// Plain output cannot clear them, so the cursor moves on to a new row instead.
+    fn clear_edit_area(&mut self) {
+        if self.plain {
+            let newline = self.output_newline.as_bytes();
+            self.io.print(newline);
+        } else {
+            self.print_cursor_move(self.screen_row, ASCIICode::ArrowUp);
+            self.io.putc(ASCIICode::CarriageReturn as u8);
+            self.clear_to_end_of_screen();
+        }
+        self.screen_row = 0;
+    }
+
+    /// Draws the prompt and line being edited, or the active search, on the cleared row
+    /// the cursor is at
+    fn restore_edit_area(&mut self) {
+        if self.search.is_some() {
+            self.print_search();
+        } else {
+            self.print_edit_line();
+        }
+    }
+
+    /// Emits an absolute cursor movement `ESC [ row ; column H`
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row to move to, counted from 0
+    /// * `column` - Column to move to, counted from 0
+    pub(super) fn print_cursor_position(&mut self, row: usize, column: usize) {
+        let mut buffer = itoa::Buffer::new();
+        self.print_escape_sequence();
+        self.io.print(buffer.format(row + 1).as_bytes());
+        self.io.putc(ASCIICode::Semicolon as u8);
+        self.io.print(buffer.format(column + 1).as_bytes());
+        self.io.putc(ASCIICode::CursorPosition as u8);
+    }
+
+    /// Emits a relative cursor movement `ESC [ n <direction>`
+    ///
+    /// The count is omitted for single-cell moves, as terminals default it to 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Number of rows or columns to move
+    /// * `direction` - Final byte selecting the direction (`ArrowUp`, `ArrowDown`,
+    ///   `ArrowLeft` or `ArrowRight`)
+    fn print_cursor_move(&mut self, count: usize, direction: ASCIICode) {
+        if count == 0 {
+            return;
+        }
+
+        self.print_escape_sequence();
+        if count > 1 {
+            let mut buffer = itoa::Buffer::new();
+            self.io.print(buffer.format(count).as_bytes());
+        }
+        self.io.putc(direction as u8);
+    }
+
+    /// Returns the number of columns the line being edited wraps at
+    ///
+    /// Plain output cannot move the cursor between rows, so its lines are taken never to
+    /// wrap.
+    fn line_width(&self) -> usize {
+        if self.plain { usize::MAX } else { self.width }
+    }
+
+    /// Returns the screen offset of a byte position in the current line
+    ///
+    /// The offset counts cells from the start of the prompt, with rows of the terminal
+    /// width, see [`wrapped_offset`].
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Byte position on a character boundary
+    fn screen_offset(&self, index: usize) -> usize {
+        let width = self.line_width();
+        let prompt_end = wrapped_offset(0, self.displayed_prompt.text.as_bytes(), width);
+        let text = &self.current_line[..index];
+        match self.echo() {
+            Echo::Normal => wrapped_offset(prompt_end, text, width),
+            Echo::Masked => prompt_end + char_count(text),
+            Echo::Hidden => prompt_end,
+        }
+    }
+
+    /// Prints part of the line buffer, as the echo mode shows it
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Byte position of the first character to print
+    /// * `end` - Byte position after the last character to print
+    fn print_line_text(&mut self, start: usize, end: usize) {
+        match self.echo() {
+            Echo::Normal => self.io.print(&self.current_line[start..end]),
+            Echo::Masked => {
+                for _ in 0..char_count(&self.current_line[start..end]) {
+                    self.io.putc(b'*');
+                }
+            }
+            Echo::Hidden => {}
+        }
+    }
+
+    /// Moves the terminal cursor between two screen offsets of the current line with at
+    /// most one vertical and one horizontal movement
+    ///
+    /// Plain output moves left with backspaces and right by printing the characters
+    /// passed over again.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Screen offset the terminal cursor is currently at
+    /// * `to` - Screen offset to move the terminal cursor to
+    fn move_screen_cursor(&mut self, from: usize, to: usize) {
+        if self.plain {
+            for _ in to..from {
+                self.io.putc(ASCIICode::Backspace as u8);
+            }
+            if from < to {
+                let (start, end) = (self.index_at_offset(from), self.index_at_offset(to));
+                self.print_line_text(start, end);
+            }
+            return;
+        }
+
+        let (from_row, from_column) = (from / self.width, from % self.width);
+        let (to_row, to_column) = (to / self.width, to % self.width);
+
+        if to_row < from_row {
+            self.print_cursor_move(from_row - to_row, ASCIICode::ArrowUp);
+        } else {
+            self.print_cursor_move(to_row - from_row, ASCIICode::ArrowDown);
+        }
+        if to_column < from_column {
+            self.print_cursor_move(from_column - to_column, ASCIICode::ArrowLeft);
+        } else {
+            self.print_cursor_move(to_column - from_column, ASCIICode::ArrowRight);
+        }
+        self.screen_row = to_row;
+    }
+
+    /// Returns the byte position of the first character of the current line at or after
+    /// a screen offset
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Screen offset within the displayed line
+    fn index_at_offset(&self, offset: usize) -> usize {
+        let line = &self.current_line;
+        let mut index = 0;
+        while index < line.len() && self.screen_offset(index) < offset {
+            index = next_boundary(line, index);
+        }
+        index
+    }
+
+    /// Records where the terminal cursor is after printing text that ended at a screen
+    /// offset
+    ///
+    /// Terminals leave the cursor in the last column of a filled row until the next
+    /// character arrives, so in that case it is moved to the start of the next row
+    /// explicitly to match the offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Screen offset just after the printed text
+    fn finish_print(&mut self, offset: usize) {
+        let width = self.line_width();
+        if offset > 0 && offset.is_multiple_of(width) {
+            self.io.putc(ASCIICode::CarriageReturn as u8);
+            self.io.putc(ASCIICode::Newline as u8);
+        }
+        self.screen_row = offset / width;
+    }
+
+    /// Moves the cursor one character to the left
+    fn move_cursor_left(&mut self) {
+        if self.cursor > 0 {
+            self.move_cursor_to(previous_boundary(&self.current_line, self.cursor));
+        }
+    }
+
+    /// Moves the cursor one character to the right
+    fn move_cursor_right(&mut self) {
+        if self.cursor < self.current_line.len() {
+            self.move_cursor_to(next_boundary(&self.current_line, self.cursor));
+        }
+    }
+
+    /// Returns whether a byte of the line separates words
+    ///
+    /// Bytes of multi-byte characters are never separators, so word boundaries are
+    /// always character boundaries.
+    fn is_word_separator(&self, byte: u8) -> bool {
+        byte.is_ascii() && self.word_separators.contains(&byte)
+    }
+
+    /// Returns the start of the word before the cursor
+    ///
+    /// Separators directly before the cursor are skipped first.
+    fn previous_word_start(&self) -> usize {
+        let line = &self.current_line;
+        let mut index = self.cursor;
+        while index > 0 && self.is_word_separator(line[index - 1]) {
+            index -= 1;
+        }
+        while index > 0 && !self.is_word_separator(line[index - 1]) {
+            index -= 1;
+        }
+        index
+    }
+
+    /// Returns the end of the word after the cursor
+    ///
+    /// Separators directly after the cursor are skipped first.
+    fn next_word_end(&self) -> usize {
+        let line = &self.current_line;
+        let mut index = self.cursor;
+        while index < line.len() && self.is_word_separator(line[index]) {
+            index += 1;
+        }
+        while index < line.len() && !self.is_word_separator(line[index]) {
+            index += 1;
+        }
+        index
+    }
+
+    /// Returns the start of the word after the one the cursor is in (vi `w`)
+    ///
+    /// Separators after the cursor's word are skipped as well.
+    fn next_word_start(&self) -> usize {
+        let line = &self.current_line;
+        let mut index = self.cursor;
+        while index < line.len() && !self.is_word_separator(line[index]) {
+            index += 1;
+        }
+        while index < line.len() && self.is_word_separator(line[index]) {
+            index += 1;
+        }
+        index
+    }
+
+    /// Moves the cursor to the start of the previous word (Alt-B, Ctrl-Left)
+    fn move_word_left(&mut self) {
+        self.move_cursor_to(self.previous_word_start());
+    }
+
+    /// Moves the cursor to the end of the next word (Alt-F, Ctrl-Right)
+    fn move_word_right(&mut self) {
+        self.move_cursor_to(self.next_word_end());
+    }
+
+    /// Moves the cursor to the beginning of the line
+    fn move_cursor_to_start(&mut self) {
+        self.move_cursor_to(0);
+    }
+
+    /// Moves the cursor to the end of the line
+    fn move_cursor_to_end(&mut self) {
+        self.move_cursor_to(self.current_line.len());
+    }
+
+    /// Moves the cursor to a byte position of the current line
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Byte position on a character boundary
+    fn move_cursor_to(&mut self, index: usize) {
+        let from = self.screen_offset(self.cursor);
+        let to = self.screen_offset(index);
+        self.move_screen_cursor(from, to);
+        self.cursor = index;
+    }
+
+    /// Returns the screen offsets of the terminal cursor and of the end of the line as
+    /// displayed
+    ///
+    /// Taken before the line buffer is modified and passed to [`Self::refresh_from`].
+    fn screen_state(&self) -> (usize, usize) {
+        (
+            self.screen_offset(self.cursor),
+            self.screen_offset(self.current_line.len()),
+        )
+    }
+
+    /// Brings the display up to date after the line buffer changed from `start` onwards
+    ///
+    /// Only the changed suffix is written. The rest of the row, or of the screen if the
+    /// line now ends on an earlier row, is cleared if the line got shorter, and the
+    /// terminal cursor is left at `self.cursor`. Plain output blanks the cells the line
+    /// no longer covers with spaces instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - First byte position of the line buffer that changed
+    /// * `screen_state` - Screen offsets of the cursor and the line end before the change,
+    ///   see [`Self::screen_state`]
+    fn refresh_from(&mut self, start: usize, (screen_offset, old_end): (usize, usize)) {
+        let start_offset = self.screen_offset(start);
+        self.move_screen_cursor(screen_offset, start_offset);
+
+        let end = self.screen_offset(self.current_line.len());
+        if start < self.current_line.len() && self.echo() != Echo::Hidden {
+            self.print_line_text(start, self.current_line.len());
+            self.finish_print(end);
+        }
+
+        let cursor_offset = self.screen_offset(self.cursor);
+        if self.plain {
+            for _ in end..old_end {
+                self.io.putc(ASCIICode::Space as u8);
+            }
+            self.move_screen_cursor(end.max(old_end), cursor_offset);
+            return;
+        }
+        if old_end / self.width > end / self.width {
+            self.clear_to_end_of_screen();
+        } else if old_end > end {
+            self.print_control_sequence(ASCIIControl::ClearToEndOfLine.as_bytes());
+        }
+        self.move_screen_cursor(end, cursor_offset);
+    }
+
+    /// Redraws the prompt and the whole line, starting on the current row
+    ///
+    /// Used when the contents of the rows are unknown, e.g. after the screen was cleared
+    /// or other output was printed.
+    fn redraw_line(&mut self) {
+        self.clear_edit_area();
+        self.print_edit_line();
+    }
+
+    /// Prints the prompt and the whole line on the cleared row the cursor is at
+    fn print_edit_line(&mut self) {
+        self.print_prompt();
+
+        let end = self.screen_offset(self.current_line.len());
+        let cursor_offset = self.screen_offset(self.cursor);
+        self.print_line_text(0, self.current_line.len());
+        self.finish_print(end);
+        self.move_screen_cursor(end, cursor_offset);
+    }
+
+    /// Moves the cursor to the beginning of the next line and runs the submitted command,
+    /// or passes the line to the handler that requested it
+    ///
+    /// The command output is printed before the next prompt, paged once the terminal
+    /// height is known.
+    fn newline(&mut self) {
+        // Leave the cursor below all rows of a wrapped line
+        self.move_cursor_to_end();
+        self.print_newline();
+
+        // Take the line out of the editor so the handler is free to use the terminal
+        let mut line = self.current_line.clone();
+        self.discard_line();
+
+        self.pager = self.output_rows().map(|rows| Pager::new(rows, self.width));
+        if let Some(request) = self.line_request.take() {
+            if let Ok(text) = core::str::from_utf8(&line) {
+                self.exit_status = 0;
+                (request.handler)(self, Some(text));
+            }
+            if request.echo != Echo::Normal {
+                wipe(&mut line);
+            }
+        } else {
+            self.history.push(&line);
+            self.execute_line(&line);
+        }
+        self.reset_vi_mode();
+        if !self.output_paused {
+            self.continue_output();
+        }
+    }
+
+    /// Tokenizes a submitted line and runs the matching command
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The submitted line
+    fn execute_line(&mut self, line: &[u8]) {
+        let Ok(line) = core::str::from_utf8(line) else {
+            return;
+        };
+
+        let mut tokens = line.split_ascii_whitespace();
+        let Some(name) = tokens.next() else {
+            return;
+        };
+
+        let mut args: heapless::Vec<&str, MAX_COMMAND_ARGS> = heapless::Vec::new();
+        for token in tokens {
+            if args.push(token).is_err() {
+                self.print_styled_line(b"error: too many arguments", Style::ERROR);
+                self.exit_status = EXIT_USAGE;
+                return;
+            }
+        }
+
+        self.exit_status = 0;
+        match find_command(self.commands, name) {
+            Some(command) => (command.handler)(self, &args),
+            None => {
+                cli_println!(self, error, "unknown command: {} (try 'help')", name);
+                self.exit_status = EXIT_UNKNOWN_COMMAND;
+            }
+        }
+    }
+
+    /// Replaces the current line with the given text and redraws it with the cursor at the end
+    ///
+    /// The replaced line cannot be restored with undo.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - Text to place in the line buffer
+    fn replace_line(&mut self, line: &[u8]) {
+        self.journal.clear();
+        self.set_line(line, line.len());
+    }
+
+    /// Replaces the line buffer and cursor, redrawing only what changed
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - Text to place in the line buffer
+    /// * `cursor` - Byte position of the cursor in the new line
+    fn set_line(&mut self, line: &[u8], cursor: usize) {
+        let screen_state = self.screen_state();
+        let mut unchanged = self
+            .current_line
+            .iter()
+            .zip(line)
+            .take_while(|(old, new)| old == new)
+            .count();
+        while !is_boundary(line, unchanged) {
+            unchanged -= 1;
+        }
+
+        self.current_line.clear();
+        let _ = self.current_line.extend_from_slice(line);
+        self.cursor = cursor;
+        self.refresh_from(unchanged, screen_state);
+    }
+
+    /// Returns whether the line being edited is a command, rather than a requested line
+    /// for which history and completion do not apply
+    fn editing_command(&self) -> bool {
+        self.line_request.is_none()
+    }
+
+    /// Recalls the previous (older) history entry into the current line
+    fn history_previous(&mut self) {
+        if !self.editing_command() {
+            return;
+        }
+        let Some(line) = self.history.previous(&self.current_line) else {
+            return;
+        };
+        let recalled = Line::from_slice(line).unwrap_or_default();
+        self.replace_line(&recalled);
+    }
+
+    /// Recalls the next (newer) history entry, or the draft line, into the current line
+    fn history_next(&mut self) {
+        if !self.editing_command() {
+            return;
+        }
+        let Some(line) = self.history.next() else {
+            return;
+        };
+        let recalled = Line::from_slice(line).unwrap_or_default();
+        self.replace_line(&recalled);
+    }
+
+    /// Deletes the previous character and moves the cursor left
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.record_edit();
+            let screen_state = self.screen_state();
+            let start = previous_boundary(&self.current_line, self.cursor);
+            self.remove_range(start, self.cursor);
+            self.cursor = start;
+            self.refresh_from(start, screen_state);
+        }
+    }
+
+    /// Deletes the character under the cursor
+    fn delete_character(&mut self) {
+        if self.cursor < self.current_line.len() {
+            self.record_edit();
+            let screen_state = self.screen_state();
+            let end = next_boundary(&self.current_line, self.cursor);
+            self.remove_range(self.cursor, end);
+            self.refresh_from(self.cursor, screen_state);
+        }
+    }
+
+    /// Removes the characters in `start..end` from the line buffer
+    ///
+    /// The caller is responsible for positioning the cursor and redrawing the line.
+    fn remove_range(&mut self, start: usize, end: usize) {
+        let len = self.current_line.len();
+        self.current_line.copy_within(end..len, start);
+        self.current_line.truncate(len - (end - start));
+    }
+
+    /// Removes `start..end` from the line into the kill ring, leaving the cursor at `start`
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Byte position of the first removed character
+    /// * `end` - Byte position after the last removed character
+    fn kill_range(&mut self, start: usize, end: usize) {
+        if start < end {
+            self.record_edit();
+            if self.echo() == Echo::Normal {
+                self.kill_ring.push(&self.current_line[start..end]);
+            }
+            let screen_state = self.screen_state();
+            self.remove_range(start, end);
+            self.cursor = start;
+            self.refresh_from(start, screen_state);
+        }
+    }
+
+    /// Deletes everything from the cursor to the end of the line (Ctrl-K)
+    fn kill_to_end(&mut self) {
+        self.kill_range(self.cursor, self.current_line.len());
+    }
+
+    /// Deletes everything from the beginning of the line to the cursor (Ctrl-U)
+    fn kill_to_start(&mut self) {
+        self.kill_range(0, self.cursor);
+    }
+
+    /// Deletes from the cursor to the end of the next word (Alt-D)
+    fn kill_word_forward(&mut self) {
+        self.kill_range(self.cursor, self.next_word_end());
+    }
+
+    /// Deletes from the start of the previous word to the cursor (Alt-Backspace)
+    fn kill_word_backward(&mut self) {
+        self.kill_range(self.previous_word_start(), self.cursor);
+    }
+
+    /// Deletes the word before the cursor, including trailing spaces (Ctrl-W)
+    fn delete_word_backward(&mut self) {
+        let end = self.cursor;
+        let mut start = end;
+        while start > 0 && self.current_line[start - 1] == ASCIICode::Space as u8 {
+            start -= 1;
+        }
+        while start > 0 && self.current_line[start - 1] != ASCIICode::Space as u8 {
+            start -= 1;
+        }
+
+        self.kill_range(start, end);
+    }
+
+    /// Clears the screen and redraws the prompt and current line (Ctrl-L)
+    pub(super) fn redraw_screen(&mut self) {
+        let line = self.current_line.clone();
+        let cursor = self.cursor;
+
+        self.clear_screen();
+        self.current_line = line;
+        self.cursor = cursor;
+        self.redraw_line();
+    }
+
+    /// Empties the line buffer and forgets its undo states
+    ///
+    /// The text of a masked or hidden line is overwritten rather than only dropped, so
+    /// secrets do not linger in memory after they were entered.
+    fn discard_line(&mut self) {
+        if self.echo() == Echo::Normal {
+            self.current_line.clear();
+            self.journal.clear();
+        } else {
+            wipe(&mut self.current_line);
+            self.journal.wipe();
+        }
+        self.cursor = 0;
+    }
+
+    /// Abandons the current line and starts a fresh prompt (Ctrl-C)
+    fn abandon_line(&mut self) {
+        self.move_cursor_to_end();
+        self.print(b"^C", false);
+        self.print_newline();
+        self.discard_line();
+        self.history.reset_navigation();
+        self.exit_status = EXIT_INTERRUPTED;
+        if let Some(request) = self.line_request.take() {
+            (request.handler)(self, None);
+        }
+        self.reset_vi_mode();
+        self.print_prompt();
+    }
+
+    /// Starts the next line in vi insert mode
+    fn reset_vi_mode(&mut self) {
+        if let Some(vi) = &mut self.vi {
+            vi.set_mode(ViMode::Insert);
+        }
+    }
+
+    /// Switches the vi editing mode and redraws the prompt to show it
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - Mode to switch to
+    fn set_vi_mode(&mut self, mode: ViMode) {
+        let Some(vi) = &mut self.vi else {
+            return;
+        };
+        let changed = vi.mode() != mode;
+        vi.set_mode(mode);
+        if changed {
+            self.redraw_line();
+        }
+    }
+
+    /// Switches from vi insert mode to normal mode (Esc)
+    ///
+    /// As in vi, the cursor steps back onto the last character inserted.
+    fn vi_escape(&mut self) {
+        if self
+            .vi
+            .as_ref()
+            .is_some_and(|vi| vi.mode() == ViMode::Insert)
+        {
+            self.cursor = previous_boundary(&self.current_line, self.cursor);
+            self.set_vi_mode(ViMode::Normal);
+        }
+    }
+
+    /// Returns the byte position a vi motion moves the cursor to
+    ///
+    /// # Arguments
+    ///
+    /// * `motion` - Motion of a normal-mode command
+    fn motion_target(&self, motion: Motion) -> usize {
+        match motion {
+            Motion::Left => previous_boundary(&self.current_line, self.cursor),
+            Motion::Right => next_boundary(&self.current_line, self.cursor),
+            Motion::NextWordStart => self.next_word_start(),
+            Motion::WordEnd => self.next_word_end(),
+            Motion::PreviousWordStart => self.previous_word_start(),
+            Motion::LineStart => 0,
+            Motion::LineEnd => self.current_line.len(),
+        }
+    }
+
+    /// Handles a printable key typed in vi normal mode
+    ///
+    /// Outside insert mode the cursor rests on a character, so it never stays after the
+    /// last one.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The typed ASCII character
+    fn process_vi_key(&mut self, key: u8) {
+        let Some(command) = self.vi.as_mut().and_then(|vi| vi.feed(key)) else {
+            return;
+        };
+        let last_character = previous_boundary(&self.current_line, self.current_line.len());
+
+        let insert = match command {
+            ViCommand::Move(motion) => {
+                self.move_cursor_to(self.motion_target(motion).min(last_character));
+                false
+            }
+            ViCommand::Delete(motion) | ViCommand::Change(motion) => {
+                let target = self.motion_target(motion);
+                self.kill_range(self.cursor.min(target), self.cursor.max(target));
+                matches!(command, ViCommand::Change(_))
+            }
+            ViCommand::DeleteLine | ViCommand::ChangeLine => {
+                self.kill_range(0, self.current_line.len());
+                command == ViCommand::ChangeLine
+            }
+            ViCommand::Insert => true,
+            ViCommand::Append => {
+                self.move_cursor_right();
+                true
+            }
+            ViCommand::AppendAtEnd => {
+                self.move_cursor_to_end();
+                true
+            }
+            ViCommand::HistoryPrevious => {
+                self.history_previous();
+                false
+            }
+            ViCommand::HistoryNext => {
+                self.history_next();
+                false
+            }
+        };
+
+        if insert {
+            self.set_vi_mode(ViMode::Insert);
+        } else if self.cursor == self.current_line.len() {
+            self.move_cursor_left();
+        }
+    }
+
+    /// Inserts text at the cursor position, stopping at the last whole character that fits
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Valid UTF-8 bytes to insert
+    fn insert_text(&mut self, text: &[u8]) {
+        let screen_state = self.screen_state();
+        let start = self.cursor;
+        if self.insert_bytes(text) > 0 {
+            self.refresh_from(start, screen_state);
+        }
+    }
+
+    /// Inserts text into the line buffer at the cursor without redrawing
+    ///
+    /// The caller is responsible for redrawing the line.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Valid UTF-8 bytes to insert
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes inserted, which stops at the last whole character that fits
+    fn insert_bytes(&mut self, text: &[u8]) -> usize {
+        let room = self.current_line.capacity() - self.current_line.len();
+        let mut count = text.len().min(room);
+        while !is_boundary(text, count) {
+            count -= 1;
+        }
+
+        let start = self.cursor;
+        let _ = self.current_line.extend_from_slice(&text[..count]);
+        self.current_line[start..].rotate_right(count);
+        self.cursor += count;
+        count
+    }
+
+    /// Records the current line for undo before an edit
+    fn record_edit(&mut self) {
+        self.journal.record(&self.current_line, self.cursor);
+    }
+
+    /// Reverts the most recent edit (Ctrl-_)
+    fn undo(&mut self) {
+        if let Some(snapshot) = self.journal.undo(&self.current_line, self.cursor) {
+            self.set_line(&snapshot.line, snapshot.cursor);
+        }
+    }
+
+    /// Reapplies the most recently undone edit (Alt-_)
+    fn redo(&mut self) {
+        if let Some(snapshot) = self.journal.redo(&self.current_line, self.cursor) {
+            self.set_line(&snapshot.line, snapshot.cursor);
+        }
+    }
+
+    /// Inserts the most recently killed text at the cursor (Ctrl-Y)
+    fn yank(&mut self) {
+        let Some(text) = self.kill_ring.get(0) else {
+            return;
+        };
+        let text = Line::from_slice(text).unwrap_or_default();
+
+        self.record_edit();
+        let start = self.cursor;
+        self.insert_text(&text);
+        self.last_action = LastAction::Yank {
+            start,
+            end: self.cursor,
+            steps_back: 0,
+        };
+    }
+
+    /// Replaces the text just yanked with the next older kill ring entry (Alt-Y)
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - Command handled for the previous key; must be a yank
+    fn yank_pop(&mut self, previous: LastAction) {
+        let LastAction::Yank {
+            start,
+            end,
+            steps_back,
+        } = previous
+        else {
+            return;
+        };
+        let steps_back = steps_back + 1;
+        let Some(text) = self.kill_ring.get(steps_back) else {
+            return;
+        };
+        let text = Line::from_slice(text).unwrap_or_default();
+
+        let screen_state = self.screen_state();
+        self.remove_range(start, end);
+        self.cursor = start;
+        let inserted = self.insert_bytes(&text);
+        self.refresh_from(start, screen_state);
+        self.last_action = LastAction::Yank {
+            start,
+            end: start + inserted,
+            steps_back,
+        };
+    }
+
+    /// Completes the word before the cursor from the command definitions (Tab)
+    ///
+    /// A unique match is inserted followed by a space. Several matches are extended
+    /// to their longest common prefix, or listed below the line if no progress can be made.
+    fn complete(&mut self) {
+        if !self.editing_command() {
+            return;
+        }
+        let line = self.current_line.clone();
+        let Ok(before_cursor) = core::str::from_utf8(&line[..self.cursor]) else {
+            return;
+        };
+
+        let word_start = before_cursor.rfind(' ').map_or(0, |index| index + 1);
+        let prefix = &before_cursor[word_start..];
+        let mut preceding: heapless::Vec<&str, MAX_COMMAND_ARGS> = heapless::Vec::new();
+        for word in before_cursor[..word_start].split_ascii_whitespace() {
+            if preceding.push(word).is_err() {
+                return;
+            }
+        }
+
+        let candidates = completion_candidates(self.commands, &preceding, prefix);
+        match candidates.as_slice() {
+            [] => {}
+
+            [only] => {
+                self.record_edit();
+                self.insert_text(&only.as_bytes()[prefix.len()..]);
+                if self.current_line.get(self.cursor) != Some(&(ASCIICode::Space as u8)) {
+                    self.insert_text(b" ");
+                } else {
+                    self.move_cursor_right();
+                }
+            }
+
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.len(), |common, candidate| {
+                    first
+                        .bytes()
+                        .zip(candidate.bytes())
+                        .take(common)
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                });
+
+                if common > prefix.len() {
+                    self.record_edit();
+                    self.insert_text(&first.as_bytes()[prefix.len()..common]);
+                } else {
+                    self.list_candidates(&candidates);
+                }
+            }
+        }
+    }
+
+    /// Prints completion candidates below the current line and redraws the prompt
+    ///
+    /// # Arguments
+    ///
+    /// * `candidates` - Candidates to list
+    fn list_candidates(&mut self, candidates: &[&str]) {
+        let (cursor_offset, end) = self.screen_state();
+        self.move_screen_cursor(cursor_offset, end);
+        self.print_newline();
+        for (index, candidate) in candidates.iter().enumerate() {
+            if index > 0 {
+                self.io.print(b"  ");
+            }
+            self.io.print(candidate.as_bytes());
+        }
+        self.print_newline();
+        self.screen_row = 0;
+        self.print_edit_line();
+    }
+
+    /// Inserts a typed character at the current cursor position
+    ///
+    /// Consecutive characters up to a space are undone together.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Encoding of the character
+    /// * `previous` - Command handled for the previous key
+    fn insert_character(&mut self, text: &[u8], previous: LastAction) {
+        if previous != LastAction::Typing || text == [ASCIICode::Space as u8] {
+            self.record_edit();
+        }
+        self.insert_text(text);
+        self.last_action = LastAction::Typing;
+    }
+
+    /// Feeds a non-ASCII byte to the UTF-8 decoder and inserts the completed character
+    ///
+    /// Bytes of invalid sequences are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The received byte
+    /// * `previous` - Command handled for the previous key
+    fn insert_utf8_byte(&mut self, data: u8, previous: LastAction) {
+        match self.utf8_decoder.feed(data) {
+            DecodeResult::Complete(_, bytes) => self.insert_character(&bytes, previous),
+            // Keep grouping typed text across the bytes of a character
+            DecodeResult::Pending => self.last_action = previous,
+            DecodeResult::Invalid => {}
+        }
+    }
+
+    /// Starts a reverse incremental history search (Ctrl-R)
+    fn start_search(&mut self) {
+        if !self.editing_command() {
+            return;
+        }
+        self.search = Some(ReverseSearch {
+            query: Line::new(),
+            found: None,
+        });
+        self.draw_search();
+    }
+
+    /// Redraws the search prompt, query and matching entry on the current row
+    fn draw_search(&mut self) {
+        self.clear_edit_area();
+        self.print_search();
+    }
+
+    /// Prints the search prompt, query and matching entry on the cleared row the cursor
+    /// is at
+    fn print_search(&mut self) {
+        let Some(search) = &self.search else {
+            return;
+        };
+        let query = search.query.clone();
+        let found = search.found;
+        let failed = found.is_none() && !query.is_empty();
+        let label: &[u8] = if failed {
+            b"(failed reverse-i-search)`"
+        } else {
+            b"(reverse-i-search)`"
+        };
+        let separator = b"': ";
+        let entry = found
+            .and_then(|steps_back| self.history.entry(steps_back))
+            .and_then(|entry| Line::from_slice(entry).ok())
+            .unwrap_or_default();
+
+        self.print(label, true);
+        self.io.print(&query);
+        self.print(separator, true);
+        self.io.print(&entry);
+
+        let width = self.line_width();
+        let end = [label, &query, separator, &entry]
+            .iter()
+            .fold(0, |offset, text| wrapped_offset(offset, text, width));
+        self.finish_print(end);
+    }
+
+    /// Searches the history again for the current query and redraws the search
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Number of steps back from the most recent entry to start searching at
+    fn update_search(&mut self, from: usize) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+
+        if let Some(found) = self.history.search(&search.query, from) {
+            search.found = Some(found);
+        } else if from == 0 || search.query.is_empty() {
+            search.found = None;
+        }
+        // Stepping past the oldest match keeps the current one, as readline does
+
+        self.draw_search();
+    }
+
+    /// Ends the search, leaving the matching entry in the line buffer when `accept` is set
+    ///
+    /// # Arguments
+    ///
+    /// * `accept` - Whether to take over the match, or restore the line as it was
+    fn finish_search(&mut self, accept: bool) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+
+        if accept
+            && let Some(entry) = search
+                .found
+                .and_then(|steps_back| self.history.entry(steps_back))
+        {
+            let entry = Line::from_slice(entry).unwrap_or_default();
+            self.current_line = entry;
+            self.cursor = self.current_line.len();
+            self.journal.clear();
+            self.history.reset_navigation();
+        }
+
+        self.redraw_line();
+    }
+
+    /// Handles a byte received while a reverse incremental search is active
+    ///
+    /// Printable characters extend the query, Backspace shortens it and Ctrl-R steps to
+    /// the next older match. Ctrl-G and Esc restore the original line. Any other key
+    /// accepts the match and is then processed as usual, so Enter runs it.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The received byte
+    ///
+    /// # Returns
+    ///
+    /// `true` if the byte was consumed by the search
+    fn process_search_byte(&mut self, data: u8) -> bool {
+        let Some(search) = &mut self.search else {
+            return false;
+        };
+        let found = search.found;
+
+        match data {
+            x if x == ASCIICode::CtrlR as u8 => {
+                self.update_search(found.map_or(0, |steps_back| steps_back + 1));
+            }
+
+            x if x == ASCIICode::CtrlG as u8 => self.finish_search(false),
+
+            x if x == ASCIICode::Escape as u8 => {
+                // Let the escape parser see the byte so a following key sequence still works
+                self.finish_search(false);
+                return false;
+            }
+
+            x if x == ASCIICode::Backspace as u8 || x == ASCIICode::Delete as u8 => {
+                let end = search.query.len();
+                search.query.truncate(previous_boundary(&search.query, end));
+                self.update_search(0);
+            }
+
+            0x20..=0x7E => {
+                let _ = search.query.push(data);
+                self.update_search(found.unwrap_or(0));
+            }
+
+            0x80..=0xFF => {
+                if let DecodeResult::Complete(_, bytes) = self.utf8_decoder.feed(data)
+                    && search.query.extend_from_slice(&bytes).is_ok()
+                {
+                    self.update_search(found.unwrap_or(0));
+                }
+            }
+
+            _ => {
+                self.finish_search(true);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Starts collecting pasted text (`ESC [ 200 ~`)
+    fn begin_paste(&mut self) {
+        self.paste = Some(Paste {
+            text: Line::new(),
+            truncated: 0,
+        });
+    }
+
+    /// Handles a byte received during a bracketed paste
+    ///
+    /// Text is collected and inserted a line at a time. Line endings submit the line
+    /// according to the input line-ending policy, tabs are pasted as spaces and other
+    /// control characters are dropped, so pasted text cannot trigger editing keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The received byte
+    fn paste_character(&mut self, data: u8) {
+        let mut encoded = [data];
+        let text: &[u8] = match data {
+            x if x == ASCIICode::CarriageReturn as u8 || x == ASCIICode::Newline as u8 => {
+                if self.accept_line_ending(data) {
+                    self.flush_paste();
+                    self.newline();
+                }
+                return;
+            }
+
+            x if x == ASCIICode::Tab as u8 => {
+                encoded[0] = ASCIICode::Space as u8;
+                &encoded
+            }
+
+            0x20..=0x7E => &encoded,
+
+            0x80..=0xFF => {
+                if let DecodeResult::Complete(_, bytes) = self.utf8_decoder.feed(data)
+                    && let Some(paste) = &mut self.paste
+                    && paste.text.extend_from_slice(&bytes).is_err()
+                {
+                    paste.truncated += bytes.len();
+                }
+                return;
+            }
+
+            _ => return,
+        };
+
+        if let Some(paste) = &mut self.paste
+            && paste.text.extend_from_slice(text).is_err()
+        {
+            paste.truncated += text.len();
+        }
+    }
+
+    /// Inserts the pasted text collected so far with a single redraw
+    fn flush_paste(&mut self) {
+        let Some(paste) = &mut self.paste else {
+            return;
+        };
+        let text = core::mem::take(&mut paste.text);
+        if text.is_empty() {
+            return;
+        }
+
+        self.record_edit();
+        let old_len = self.current_line.len();
+        self.insert_text(&text);
+        let inserted = self.current_line.len() - old_len;
+
+        if let Some(paste) = &mut self.paste {
+            paste.truncated += text.len() - inserted;
+        }
+    }
+
+    /// Finishes a bracketed paste (`ESC [ 201 ~`), reporting text that did not fit
+    fn end_paste(&mut self) {
+        self.flush_paste();
+        let Some(paste) = self.paste.take() else {
+            return;
+        };
+
+        if paste.truncated > 0 {
+            self.warn_above(format_args!(
+                "paste truncated: {} bytes did not fit on the line ({MAX_LINE_LENGTH} bytes max)",
+                paste.truncated
+            ));
+        }
+    }
+
+    /// Processes a buffer of input bytes
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - Buffer containing input bytes to process
+    pub fn process_bytes(&mut self, buffer: &[u8]) {
+        for &data in buffer.iter() {
+            if data != ASCIICode::CarriageReturn as u8 && data != ASCIICode::Newline as u8 {
+                self.pending_line_ending = None;
+            }
+
+            if self.process_search_byte(data) {
+                continue;
+            }
+
+            let escape_pending = self.escape_parser.is_escape_pending();
+            match self.escape_parser.feed(data) {
+                ParseResult::Pending | ParseResult::Discarded => {}
+                ParseResult::Complete(sequence) => self.process_escape_sequence(&sequence),
+                ParseResult::Rejected => {
+                    // An ESC followed by a control character was the Esc key
+                    if escape_pending {
+                        self.vi_escape();
+                    }
+                    self.process_character(data);
+                }
+            }
+        }
+    }
+
+    /// Applies the input line-ending policy to a received CR or LF
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The received carriage return or line feed
+    ///
+    /// # Returns
+    ///
+    /// `true` if the byte submits the current line
+    pub(super) fn accept_line_ending(&mut self, data: u8) -> bool {
+        let carriage_return = ASCIICode::CarriageReturn as u8;
+        let line_feed = ASCIICode::Newline as u8;
+        let completes_pair = self.pending_line_ending.take() == Some(data);
+
+        match self.input_line_ending {
+            InputLineEnding::Cr => data == carriage_return,
+            InputLineEnding::Lf => data == line_feed,
+            InputLineEnding::CrLf => {
+                if data == carriage_return {
+                    self.pending_line_ending = Some(line_feed);
+                }
+                completes_pair
+            }
+            InputLineEnding::Auto => {
+                if completes_pair {
+                    return false;
+                }
+                self.pending_line_ending = Some(if data == carriage_return {
+                    line_feed
+                } else {
+                    carriage_return
+                });
+                true
+            }
+        }
+    }
+
+    /// Handles a byte that is not part of an escape sequence
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The received byte
+    fn process_character(&mut self, data: u8) {
+        if data.is_ascii() {
+            // An ASCII byte cuts short any partially received UTF-8 character
+            self.utf8_decoder.reset();
+        }
+
+        if self.paste.is_some() {
+            self.paste_character(data);
+            return;
+        }
+
+        let previous = core::mem::replace(&mut self.last_action, LastAction::Other);
+        if !data.is_ascii_control()
+            && self
+                .vi
+                .as_ref()
+                .is_some_and(|vi| vi.mode() == ViMode::Normal)
+        {
+            // Bytes of non-ASCII characters are not commands
+            if data.is_ascii() {
+                self.process_vi_key(data);
+            }
+            return;
+        }
+
+        match data {
+            // Line endings rejected by the input policy fall through to be ignored
+            x if (x == ASCIICode::CarriageReturn as u8 || x == ASCIICode::Newline as u8)
+                && self.accept_line_ending(data) =>
+            {
+                self.newline();
+            }
+
+            x if x == ASCIICode::Backspace as u8 || x == ASCIICode::Delete as u8 => {
+                self.backspace();
+            }
+
+            x if x == ASCIICode::Tab as u8 => self.complete(),
+            x if x == ASCIICode::CtrlA as u8 => self.move_cursor_to_start(),
+            x if x == ASCIICode::CtrlE as u8 => self.move_cursor_to_end(),
+            x if x == ASCIICode::CtrlK as u8 => self.kill_to_end(),
+            x if x == ASCIICode::CtrlU as u8 => self.kill_to_start(),
+            x if x == ASCIICode::CtrlW as u8 => self.delete_word_backward(),
+            x if x == ASCIICode::CtrlL as u8 => self.redraw_screen(),
+            x if x == ASCIICode::CtrlC as u8 => self.abandon_line(),
+            x if x == ASCIICode::CtrlD as u8 => self.delete_character(),
+            x if x == ASCIICode::CtrlR as u8 => self.start_search(),
+            x if x == ASCIICode::CtrlY as u8 => self.yank(),
+            x if x == ASCIICode::CtrlUnderscore as u8 => self.undo(),
+
+            0x20..=0x7E => self.insert_character(&[data], previous),
+
+            0x80..=0xFF => self.insert_utf8_byte(data, previous),
+
+            _ => {}
+        }
+    }
+
+    /// Maps a complete escape sequence to an editor action
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - The received escape sequence
+    fn process_escape_sequence(&mut self, sequence: &EscapeSequence) {
+        if self.size_query_pending
+            && sequence.kind == SequenceKind::Csi
+            && sequence.final_byte == b'R'
+        {
+            // Cursor position report answering `request_size`, taken in the bottom-right corner
+            self.size_query_pending = false;
+            let (row, column) = (sequence.param(0, 0), sequence.param(1, 0));
+            if column > 0 {
+                self.width = column.into();
+            }
+            if row > 0 {
+                self.set_height(Some(row.into()));
+            }
+            if self.status_bar.is_some() {
+                self.activate_status_bar();
+            }
+            return;
+        }
+
+        let Some(event) = KeyEvent::from_sequence(sequence) else {
+            return;
+        };
+
+        if let Key::Meta(byte) = event.key
+            && self.vi.is_some()
+        {
+            // In vi mode, this is Esc typed just before another key
+            self.vi_escape();
+            self.process_character(byte);
+            return;
+        }
+
+        let previous = core::mem::replace(&mut self.last_action, LastAction::Other);
+        let word = event.modifiers.ctrl || event.modifiers.alt;
+        match event.key {
+            Key::Left if word => self.move_word_left(),
+            Key::Right if word => self.move_word_right(),
+            Key::Left => self.move_cursor_left(),
+            Key::Right => self.move_cursor_right(),
+            Key::Up => self.history_previous(),
+            Key::Down => self.history_next(),
+            Key::Home => self.move_cursor_to_start(),
+            Key::End => self.move_cursor_to_end(),
+            Key::Delete => self.delete_character(),
+            Key::PasteStart => self.begin_paste(),
+            Key::PasteEnd => self.end_paste(),
+            Key::Meta(b'b') => self.move_word_left(),
+            Key::Meta(b'f') => self.move_word_right(),
+            Key::Meta(b'd') => self.kill_word_forward(),
+            Key::Meta(byte)
+                if byte == ASCIICode::Backspace as u8 || byte == ASCIICode::Delete as u8 =>
+            {
+                self.kill_word_backward()
+            }
+            Key::Meta(b'y') => self.yank_pop(previous),
+            Key::Meta(b'_') => self.redo(),
+            _ => {}
+        }
+    }
+}
+
+impl<IO: ByteIo> fmt::Write for Terminal<IO> {
+    /// Writes text to the terminal, sending line feeds as the configured output newline
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (index, line) in s.split('\n').enumerate() {
+            if index > 0 {
+                self.print_newline();
+            }
+            self.output(line.as_bytes());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::uart::fake::{
+        FakeIo, PROMPT, draw_status, prompt, terminal, test_commands, type_keys,
+    };
+    use crate::peripherals::uart::print::cli_print;
+    use crate::peripherals::uart::status_bar::StatusPosition;
+    use crate::peripherals::uart::style::Color;
+
+    #[test]
+    fn startup_clears_screen_and_prints_banner_and_prompt() {
+        let mut terminal = Terminal::new(
+            FakeIo::default(),
+            Style::new().foreground(Color::Blue),
+            b"BANNER\r\n",
+            PROMPT,
+            test_commands(),
+        );
+
+        let expected = [
+            b"\x1b[?2004h\x1b[2J\x1b[H\x1b7\x1b[999;999H\x1b[6n\x1b8\x1b[34mBANNER\r\n\x1b[0m"
+                .as_slice(),
+            &prompt(),
+        ]
+        .concat();
+        assert_eq!(terminal.io.take_output(), expected);
+    }
+
+    #[test]
+    fn printable_characters_are_echoed() {
+        let mut terminal = terminal();
+
+        assert_eq!(type_keys(&mut terminal, b"say"), b"say");
+        assert_eq!(terminal.current_line, b"say");
+        assert_eq!(terminal.cursor, 3);
+    }
+
+    #[test]
+    fn enter_runs_command_before_next_prompt() {
+        let mut terminal = terminal();
+
+        let output = type_keys(&mut terminal, b"say hello\r");
+        let expected_tail = [b"\r\nhello\x1b[0m\r\n".as_slice(), &prompt()].concat();
+        assert!(output.ends_with(&expected_tail));
+        assert!(terminal.current_line.is_empty());
+        assert_eq!(terminal.cursor, 0);
+    }
+
+    #[test]
+    fn unknown_command_is_reported() {
+        let mut terminal = terminal();
+
+        let output = type_keys(&mut terminal, b"nope\r");
+        let expected = [
+            b"nope\r\n\x1b[31munknown command: nope (try 'help')\x1b[0m\r\n".as_slice(),
+            &prompt(),
+        ]
+        .concat();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn left_arrow_moves_cursor() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"ab");
+
+        assert_eq!(type_keys(&mut terminal, b"\x1b[D"), b"\x1b[D");
+        assert_eq!(terminal.cursor, 1);
+    }
+
+    #[test]
+    fn multi_byte_sequences_leave_no_stray_characters() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"abc");
+
+        // Home, Delete, Ctrl-Right, End
+        type_keys(&mut terminal, b"\x1b[H\x1b[3~\x1b[1;5C\x1bOF");
+        assert_eq!(terminal.current_line, b"bc");
+        assert_eq!(terminal.cursor, 2);
+    }
+
+    #[test]
+    fn up_and_down_arrows_recall_history() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"say hello\r");
+        type_keys(&mut terminal, b"status\r");
+        type_keys(&mut terminal, b"dr");
+
+        type_keys(&mut terminal, b"\x1b[A\x1b[A");
+        assert_eq!(terminal.current_line, b"say hello");
+        assert_eq!(terminal.cursor, 9);
+
+        type_keys(&mut terminal, b"\x1b[B");
+        assert_eq!(terminal.current_line, b"status");
+
+        type_keys(&mut terminal, b"\x1b[B");
+        assert_eq!(terminal.current_line, b"dr");
+    }
+
+    #[test]
+    fn control_keys_edit_line() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"one two three");
+
+        // Ctrl-W deletes the last word
+        type_keys(&mut terminal, b"\x17");
+        assert_eq!(terminal.current_line, b"one two ");
+        assert_eq!(terminal.cursor, 8);
+
+        // Ctrl-A, Ctrl-D deletes the character under the cursor
+        type_keys(&mut terminal, b"\x01\x04");
+        assert_eq!(terminal.current_line, b"ne two ");
+        assert_eq!(terminal.cursor, 0);
+
+        // Ctrl-E, Left x4, Ctrl-K kills to the end
+        type_keys(&mut terminal, b"\x05\x1b[D\x1b[D\x1b[D\x1b[D\x0b");
+        assert_eq!(terminal.current_line, b"ne ");
+        assert_eq!(terminal.cursor, 3);
+
+        // Left, Ctrl-U kills to the start
+        type_keys(&mut terminal, b"\x1b[D\x15");
+        assert_eq!(terminal.current_line, b" ");
+        assert_eq!(terminal.cursor, 0);
+    }
+
+    #[test]
+    fn word_motion_stops_at_separators() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"mem 0x2000,0x40");
+
+        // Alt-B, Ctrl-Left
+        type_keys(&mut terminal, b"\x1bb");
+        assert_eq!(terminal.cursor, 11);
+        assert_eq!(type_keys(&mut terminal, b"\x1b[1;5D"), b"\x1b[7D");
+        assert_eq!(terminal.cursor, 4);
+
+        // Alt-F, Ctrl-Right
+        type_keys(&mut terminal, b"\x1bf");
+        assert_eq!(terminal.cursor, 10);
+        type_keys(&mut terminal, b"\x1b[1;5C");
+        assert_eq!(terminal.cursor, 15);
+    }
+
+    #[test]
+    fn word_separators_are_configurable() {
+        let mut terminal = terminal();
+        assert!(terminal.set_word_separators(b" "));
+        type_keys(&mut terminal, b"mem 0x2000,0x40\x1bb");
+        assert_eq!(terminal.cursor, 4);
+
+        // Sets that are too long or not ASCII are refused
+        assert!(!terminal.set_word_separators(&[b','; MAX_WORD_SEPARATORS + 1]));
+        assert!(!terminal.set_word_separators("\u{b7}".as_bytes()));
+        assert_eq!(terminal.word_separators(), b" ");
+    }
+
+    #[test]
+    fn alt_d_and_alt_backspace_kill_words() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"gpio GP25=high");
+
+        // Alt-Backspace
+        type_keys(&mut terminal, b"\x1b\x7f");
+        assert_eq!(terminal.current_line, b"gpio GP25=");
+
+        // Ctrl-A, Alt-D removes the first word, leaving the separator
+        type_keys(&mut terminal, b"\x01\x1bd");
+        assert_eq!(terminal.current_line, b" GP25=");
+        assert_eq!(terminal.cursor, 0);
+
+        // Killed words can be yanked back
+        type_keys(&mut terminal, b"\x19");
+        assert_eq!(terminal.current_line, b"gpio GP25=");
+    }
+
+    #[test]
+    fn undo_reverts_typed_words_and_kills() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"say hello");
+
+        // Ctrl-W, then Ctrl-_ brings the word back
+        type_keys(&mut terminal, b"\x17\x1f");
+        assert_eq!(terminal.current_line, b"say hello");
+        assert_eq!(terminal.cursor, 9);
+
+        // Typed text is undone a word at a time
+        type_keys(&mut terminal, b"\x1f");
+        assert_eq!(terminal.current_line, b"say");
+        type_keys(&mut terminal, b"\x1f");
+        assert_eq!(terminal.current_line, b"");
+        assert_eq!(terminal.cursor, 0);
+
+        // Nothing left to undo
+        assert_eq!(type_keys(&mut terminal, b"\x1f"), b"");
+    }
+
+    #[test]
+    fn redo_reapplies_undone_edits() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"abc\x01\x0b");
+        assert_eq!(terminal.current_line, b"");
+
+        type_keys(&mut terminal, b"\x1f");
+        assert_eq!(terminal.current_line, b"abc");
+        assert_eq!(terminal.cursor, 0);
+
+        // Alt-_ redoes the kill
+        type_keys(&mut terminal, b"\x1b_");
+        assert_eq!(terminal.current_line, b"");
+
+        // A new edit discards the redo history
+        type_keys(&mut terminal, b"\x1fx\x1b_");
+        assert_eq!(terminal.current_line, b"xabc");
+    }
+
+    #[test]
+    fn ctrl_y_yanks_killed_text() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"one two");
+
+        // Ctrl-W, Ctrl-A, Ctrl-Y
+        type_keys(&mut terminal, b"\x17\x01\x19");
+        assert_eq!(terminal.current_line, b"twoone ");
+        assert_eq!(terminal.cursor, 3);
+
+        // The yank is undone as one edit
+        type_keys(&mut terminal, b"\x1f");
+        assert_eq!(terminal.current_line, b"one ");
+    }
+
+    #[test]
+    fn alt_y_cycles_through_older_kills() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"first \x17second \x17third\x17");
+        assert_eq!(terminal.current_line, b"");
+
+        type_keys(&mut terminal, b"\x19");
+        assert_eq!(terminal.current_line, b"third");
+
+        type_keys(&mut terminal, b"\x1by");
+        assert_eq!(terminal.current_line, b"second ");
+        assert_eq!(terminal.cursor, 7);
+
+        // The ring wraps back to the most recent kill
+        type_keys(&mut terminal, b"\x1by\x1by");
+        assert_eq!(terminal.current_line, b"third");
+
+        // Alt-Y does nothing unless the previous key yanked
+        type_keys(&mut terminal, b"\x1b[D\x1by");
+        assert_eq!(terminal.current_line, b"third");
+    }
+
+    #[test]
+    fn ctrl_c_abandons_line() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"say");
+
+        let output = type_keys(&mut terminal, b"\x03");
+        let expected = [b"^C\x1b[0m\r\n".as_slice(), &prompt()].concat();
+        assert_eq!(output, expected);
+        assert!(terminal.current_line.is_empty());
+        assert_eq!(terminal.cursor, 0);
+    }
+
+    #[test]
+    fn tab_completes_unique_command_name() {
+        let mut terminal = terminal();
+
+        assert_eq!(type_keys(&mut terminal, b"sa\t"), b"say ");
+        assert_eq!(terminal.current_line, b"say ");
+    }
+
+    #[test]
+    fn tab_extends_common_prefix_then_lists_candidates() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"say he");
+
+        type_keys(&mut terminal, b"\t");
+        assert_eq!(terminal.current_line, b"say hel");
+
+        let output = type_keys(&mut terminal, b"\t");
+        assert!(output.starts_with(b"\r\nhello  help-me\r\n"));
+        assert_eq!(terminal.current_line, b"say hel");
+    }
+
+    /// Bytes the previous full-line redraw sent to show `line_len` characters with the
+    /// cursor at `cursor`: clear, carriage return, prompt, line, then one `ESC [ D` per column
+    fn legacy_rewrite_len(line_len: usize, cursor: usize) -> usize {
+        4 + 1 + prompt().len() + line_len + 3 * (line_len - cursor)
+    }
+
+    #[test]
+    fn backspace_rewrites_only_the_tail() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"abcdef\x1b[D\x1b[D\x1b[D");
+
+        let output = type_keys(&mut terminal, b"\x08");
+        assert_eq!(output, b"\x1b[Ddef\x1b[0K\x1b[3D");
+        assert_eq!(terminal.current_line, b"abdef");
+        // The previous redraw also stepped left once before rewriting the line
+        assert!(output.len() < 3 + legacy_rewrite_len(5, 2));
+    }
+
+    #[test]
+    fn insert_at_start_of_long_line_moves_back_with_one_sequence() {
+        let mut terminal = terminal();
+        let line = [b'x'; 60];
+        type_keys(&mut terminal, &line);
+        type_keys(&mut terminal, b"\x01");
+
+        let output = type_keys(&mut terminal, b"y");
+        let expected = [b"y".as_slice(), &line, b"\x1b[60D"].concat();
+        assert_eq!(output, expected);
+        assert!(output.len() < legacy_rewrite_len(61, 1));
+    }
+
+    #[test]
+    fn home_and_end_emit_a_single_movement() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"say hello");
+
+        assert_eq!(type_keys(&mut terminal, b"\x1b[H"), b"\x1b[9D");
+        assert_eq!(type_keys(&mut terminal, b"\x1b[F"), b"\x1b[9C");
+        assert_eq!(terminal.cursor, 9);
+    }
+
+    #[test]
+    fn history_recall_keeps_common_prefix() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"say hello\r");
+        type_keys(&mut terminal, b"say help-me\r");
+
+        type_keys(&mut terminal, b"\x1b[A");
+        let output = type_keys(&mut terminal, b"\x1b[A");
+        assert_eq!(output, b"\x1b[4Dlo\x1b[0K");
+        assert_eq!(terminal.current_line, b"say hello");
+    }
+
+    #[test]
+    fn crlf_from_host_submits_one_line() {
+        let mut terminal = terminal();
+        let output = type_keys(&mut terminal, b"say hello\r\n\r\n");
+
+        let expected = [
+            b"say hello\r\nhello\x1b[0m\r\n".as_slice(),
+            &prompt(),
+            b"\r\n",
+            &prompt(),
+        ]
+        .concat();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn line_feed_split_across_reads_is_still_collapsed() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"say hello\r");
+
+        assert_eq!(type_keys(&mut terminal, b"\n"), b"");
+    }
+
+    #[test]
+    fn input_line_ending_policies() {
+        let mut terminal = terminal();
+
+        terminal.set_input_line_ending(InputLineEnding::Cr);
+        type_keys(&mut terminal, b"ab\n");
+        assert_eq!(terminal.current_line, b"ab");
+        type_keys(&mut terminal, b"\r");
+        assert_eq!(terminal.current_line, b"");
+
+        terminal.set_input_line_ending(InputLineEnding::Lf);
+        type_keys(&mut terminal, b"ab\r");
+        assert_eq!(terminal.current_line, b"ab");
+        type_keys(&mut terminal, b"\n");
+        assert_eq!(terminal.current_line, b"");
+
+        terminal.set_input_line_ending(InputLineEnding::CrLf);
+        type_keys(&mut terminal, b"ab\r");
+        assert_eq!(terminal.current_line, b"ab");
+        type_keys(&mut terminal, b"\n");
+        assert_eq!(terminal.current_line, b"");
+    }
+
+    #[test]
+    fn output_newline_policy_applies_to_command_output() {
+        let mut terminal = terminal();
+        terminal.set_output_newline(OutputNewline::Lf);
+
+        let output = type_keys(&mut terminal, b"say hello\r");
+        let expected = [b"say hello\nhello\x1b[0m\n".as_slice(), &prompt()].concat();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn multi_byte_characters_are_inserted_whole() {
+        let mut terminal = terminal();
+
+        let output = type_keys(&mut terminal, "20°C µs".as_bytes());
+        assert_eq!(output, "20°C µs".as_bytes());
+        assert_eq!(terminal.current_line, "20°C µs".as_bytes());
+    }
+
+    #[test]
+    fn cursor_moves_by_code_point_and_wide_characters_take_two_columns() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, "a漢°".as_bytes());
+
+        assert_eq!(type_keys(&mut terminal, b"\x1b[D"), b"\x1b[D");
+        assert_eq!(terminal.cursor, 4);
+        assert_eq!(type_keys(&mut terminal, b"\x1b[D"), b"\x1b[2D");
+        assert_eq!(terminal.cursor, 1);
+        assert_eq!(type_keys(&mut terminal, b"\x1b[F"), b"\x1b[3C");
+    }
+
+    #[test]
+    fn backspace_removes_a_whole_code_point() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, "x漢y".as_bytes());
+        type_keys(&mut terminal, b"\x1b[D");
+
+        let output = type_keys(&mut terminal, b"\x08");
+        assert_eq!(output, b"\x1b[2Dy\x1b[0K\x1b[D");
+        assert_eq!(terminal.current_line, b"xy");
+        assert_eq!(terminal.cursor, 1);
+    }
+
+    #[test]
+    fn invalid_utf8_is_rejected() {
+        let mut terminal = terminal();
+
+        // Stray continuation, truncated sequence cut short by ASCII, overlong encoding
+        let output = type_keys(&mut terminal, b"\x80\xC2a\xC0\xAF");
+        assert_eq!(output, b"a");
+        assert_eq!(terminal.current_line, b"a");
+    }
+
+    /// Submits each line so it ends up in the history
+    fn with_history(lines: &[&[u8]]) -> Terminal<FakeIo> {
+        let mut terminal = terminal();
+        for line in lines {
+            type_keys(&mut terminal, line);
+            type_keys(&mut terminal, b"\r");
+        }
+        terminal
+    }
+
+    #[test]
+    fn ctrl_r_narrows_to_most_recent_match() {
+        let mut terminal = with_history(&[b"say hello", b"status", b"say help-me"]);
+
+        type_keys(&mut terminal, b"\x12");
+        let output = type_keys(&mut terminal, b"s");
+        assert!(output.ends_with(b"s\x1b[34m': \x1b[0msay help-me"));
+
+        let output = type_keys(&mut terminal, b"t");
+        assert!(output.ends_with(b"st\x1b[34m': \x1b[0mstatus"));
+    }
+
+    #[test]
+    fn ctrl_r_again_steps_to_older_matches_and_enter_runs_the_match() {
+        let mut terminal = with_history(&[b"say hello", b"status", b"say help-me"]);
+
+        type_keys(&mut terminal, b"\x12say\x12");
+        let output = type_keys(&mut terminal, b"\r");
+        let expected = [
+            b"\r\x1b[0J".as_slice(),
+            &prompt(),
+            b"say hello\r\nhello\x1b[0m\r\n",
+            &prompt(),
+        ]
+        .concat();
+        assert_eq!(output, expected);
+        assert!(terminal.search.is_none());
+    }
+
+    #[test]
+    fn failed_search_is_reported() {
+        let mut terminal = with_history(&[b"status"]);
+
+        type_keys(&mut terminal, b"\x12");
+        let output = type_keys(&mut terminal, b"x");
+        assert!(output.starts_with(b"\r\x1b[0J\x1b[34m(failed reverse-i-search)`"));
+    }
+
+    #[test]
+    fn ctrl_g_and_escape_restore_the_original_line() {
+        let mut terminal = with_history(&[b"status"]);
+        type_keys(&mut terminal, b"dr");
+
+        type_keys(&mut terminal, b"\x12st\x07");
+        assert!(terminal.search.is_none());
+        assert_eq!(terminal.current_line, b"dr");
+
+        type_keys(&mut terminal, b"\x12st");
+        let output = type_keys(&mut terminal, b"\x1b");
+        assert_eq!(output, [b"\r\x1b[0J".as_slice(), &prompt(), b"dr"].concat());
+        assert_eq!(terminal.current_line, b"dr");
+    }
+
+    #[test]
+    fn other_keys_accept_the_match_for_editing() {
+        let mut terminal = with_history(&[b"status"]);
+
+        type_keys(&mut terminal, b"\x12tat\x01");
+        assert!(terminal.search.is_none());
+        assert_eq!(terminal.current_line, b"status");
+        assert_eq!(terminal.cursor, 0);
+    }
+
+    #[test]
+    fn print_above_restores_line_and_cursor() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"abc\x1b[D\x1b[D");
+
+        terminal.print_above(b"temp 21C\nfan on");
+        let expected = [
+            b"\r\x1b[0Jtemp 21C\x1b[0m\r\nfan on\x1b[0m\r\n".as_slice(),
+            &prompt(),
+            b"abc\x1b[2D",
+        ]
+        .concat();
+        assert_eq!(terminal.io.take_output(), expected);
+        assert_eq!(terminal.cursor, 1);
+
+        // Editing continues where it left off
+        type_keys(&mut terminal, b"x");
+        assert_eq!(terminal.current_line, b"axbc");
+    }
+
+    #[test]
+    fn queued_log_messages_are_printed_before_input_is_processed() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"sa");
+
+        terminal.io.log_message(b"tick");
+        terminal.io.type_bytes(b"y");
+        terminal.process_input();
+
+        let expected = [b"\r\x1b[0Jtick\x1b[0m\r\n".as_slice(), &prompt(), b"say"].concat();
+        assert_eq!(terminal.io.take_output(), expected);
+    }
+
+    #[test]
+    fn print_above_keeps_an_active_search() {
+        let mut terminal = with_history(&[b"status"]);
+        type_keys(&mut terminal, b"\x12st");
+
+        terminal.print_above(b"tick");
+        let output = terminal.io.take_output();
+        assert!(output.ends_with(b"(reverse-i-search)`\x1b[0mst\x1b[34m': \x1b[0mstatus"));
+        assert!(terminal.search.is_some());
+    }
+
+    #[test]
+    fn size_is_taken_from_cursor_position_report() {
+        let mut terminal = terminal();
+        assert_eq!(type_keys(&mut terminal, b"\x1b[24;132R"), b"");
+        assert_eq!(terminal.width(), 132);
+        assert_eq!(terminal.height(), Some(24));
+
+        // Reports that were not asked for are ignored
+        type_keys(&mut terminal, b"\x1b[1;40R");
+        assert_eq!(terminal.width(), 132);
+    }
+
+    #[test]
+    fn size_reports_arriving_after_the_query_expired_are_ignored() {
+        let mut terminal = terminal();
+
+        // Without a timer, the query expires on the first update without an answer
+        terminal.process_input();
+        assert!(!terminal.size_query_pending);
+
+        // A later Shift-F3 uses the same sequence as a cursor position report
+        type_keys(&mut terminal, b"\x1b[1;2R");
+        assert_eq!(terminal.width(), DEFAULT_TERMINAL_WIDTH);
+        assert_eq!(terminal.height(), None);
+    }
+
+    #[test]
+    fn long_lines_wrap_onto_following_rows() {
+        let mut terminal = terminal();
+        terminal.set_width(10);
+
+        // The prompt and eight characters fill the first row
+        assert_eq!(type_keys(&mut terminal, b"abcdefgh"), b"abcdefgh\r\n");
+        assert_eq!(type_keys(&mut terminal, b"ij"), b"ij");
+
+        // Home and End move between the rows
+        assert_eq!(type_keys(&mut terminal, b"\x1b[H"), b"\x1b[A");
+        assert_eq!(type_keys(&mut terminal, b"\x1b[F"), b"\x1b[B");
+
+        // Inserting at the start rewrites both rows and returns to the first one
+        assert_eq!(
+            type_keys(&mut terminal, b"\x01X"),
+            b"\x1b[AXabcdefghij\x1b[A"
+        );
+    }
+
+    #[test]
+    fn shrinking_a_wrapped_line_clears_the_rows_below() {
+        let mut terminal = terminal();
+        terminal.set_width(10);
+        type_keys(&mut terminal, b"abcdefghij");
+
+        assert_eq!(type_keys(&mut terminal, b"\x15"), b"\x1b[A\x1b[0J");
+        assert_eq!(terminal.screen_row, 0);
+    }
+
+    #[test]
+    fn print_above_clears_every_row_of_a_wrapped_line() {
+        let mut terminal = terminal();
+        terminal.set_width(10);
+        type_keys(&mut terminal, b"abcdefghij");
+
+        terminal.print_above(b"tick");
+        let expected = [
+            b"\x1b[A\r\x1b[0Jtick\x1b[0m\r\n".as_slice(),
+            &prompt(),
+            b"abcdefghij",
+        ]
+        .concat();
+        assert_eq!(terminal.io.take_output(), expected);
+    }
+
+    #[test]
+    fn prompt_template_shows_failed_exit_status_in_red() {
+        let mut terminal = terminal();
+        assert!(terminal.set_prompt_template("[{status}]$ "));
+
+        let output = type_keys(&mut terminal, b"nope\r");
+        assert!(output.ends_with(b"\x1b[34m[\x1b[0m\x1b[31m127\x1b[0m\x1b[34m]$ \x1b[0m"));
+
+        let output = type_keys(&mut terminal, b"say\r");
+        assert!(output.ends_with(b"\x1b[34m[0]$ \x1b[0m"));
+    }
+
+    #[test]
+    fn prompt_template_expands_uart_context_and_uptime() {
+        let mut terminal = terminal();
+        terminal.io.uart_index = Some(1);
+        assert!(terminal.set_context("bench"));
+        assert!(terminal.set_prompt_template("uart{uart} {context} {uptime} {other}> "));
+
+        let output = type_keys(&mut terminal, b"\r");
+        assert!(output.ends_with(b"\x1b[34muart1 bench - {other}> \x1b[0m"));
+
+        // Cursor motion accounts for the expanded prompt
+        type_keys(&mut terminal, b"ab");
+        assert_eq!(terminal.screen_offset(0), "uart1 bench - {other}> ".len());
+    }
+
+    #[test]
+    fn oversized_prompt_settings_are_rejected() {
+        let mut terminal = terminal();
+        let long = "x".repeat(crate::constants::PROMPT_TEMPLATE_LENGTH + 1);
+        assert!(!terminal.set_prompt_template(&long));
+        assert!(!terminal.set_context(&long));
+        assert_eq!(terminal.prompt_template(), "> ");
+
+        assert!(terminal.set_prompt_template("{status}# "));
+        terminal.reset_prompt_template();
+        assert_eq!(terminal.prompt_template(), "> ");
+    }
+
+    /// Creates a terminal in vi mode with the output of its first vi prompt discarded
+    fn vi_terminal() -> Terminal<FakeIo> {
+        let mut terminal = terminal();
+        terminal.set_edit_mode(EditMode::Vi);
+        type_keys(&mut terminal, b"\r");
+        terminal
+    }
+
+    #[test]
+    fn vi_mode_is_shown_in_front_of_the_prompt() {
+        let mut terminal = terminal();
+        terminal.set_edit_mode(EditMode::Vi);
+        let output = type_keys(&mut terminal, b"\r");
+        assert!(output.ends_with(b"\x1b[34m(ins) > \x1b[0m"));
+
+        // A lone Esc switches to normal mode, with the cursor on the last character
+        type_keys(&mut terminal, b"ab");
+        terminal.io.type_bytes(b"\x1b");
+        terminal.process_input();
+        assert_eq!(
+            terminal.io.take_output(),
+            b"\r\x1b[0J\x1b[34m(cmd) > \x1b[0mab\x1b[D"
+        );
+        assert_eq!(terminal.cursor, 1);
+
+        assert_eq!(
+            type_keys(&mut terminal, b"i"),
+            b"\r\x1b[0J\x1b[34m(ins) > \x1b[0mab\x1b[D"
+        );
+
+        terminal.set_edit_mode(EditMode::Emacs);
+        let output = type_keys(&mut terminal, b"\r");
+        assert!(output.ends_with(&prompt()));
+    }
+
+    #[test]
+    fn vi_normal_mode_motions_and_edits() {
+        let mut terminal = vi_terminal();
+        let mut step = |keys: &[u8], line: &str, cursor: usize| {
+            type_keys(&mut terminal, keys);
+            assert_eq!(terminal.current_line, line.as_bytes());
+            assert_eq!(terminal.cursor, cursor);
+        };
+
+        // Esc followed at once by a key is taken as that key in normal mode
+        step(b"gpio GP25 high\x1b0", "gpio GP25 high", 0);
+        step(b"w", "gpio GP25 high", 5);
+        step(b"dw", "gpio high", 5);
+        step(b"x", "gpio igh", 5);
+        step(b"$l", "gpio igh", 7);
+        step(b"bh", "gpio igh", 4);
+        step(b"wcwlow", "gpio low", 8);
+        step(b"\x1bA!", "gpio low!", 9);
+        step(b"\x1bb", "gpio low!", 5);
+        step(b"d$", "gpio ", 4);
+        step(b"dd", "", 0);
+        step(b"aled\x1bx", "le", 1);
+    }
+
+    #[test]
+    fn vi_history_and_submission() {
+        let mut terminal = vi_terminal();
+        type_keys(&mut terminal, b"say one\rsay two\r");
+
+        type_keys(&mut terminal, b"\x1bk");
+        assert_eq!(terminal.current_line, b"say two");
+        assert_eq!(terminal.cursor, 6);
+        type_keys(&mut terminal, b"kkj");
+        assert_eq!(terminal.current_line, b"say two");
+
+        // Esc followed by Enter runs the line, and the next one starts in insert mode
+        let output = type_keys(&mut terminal, b"\x1b\r");
+        assert_eq!(output, b"\x1b[C\r\ntwo\x1b[0m\r\n\x1b[34m(ins) > \x1b[0m");
+        assert_eq!(terminal.history.entry(0), Some(b"say two".as_slice()));
+    }
+
+    #[test]
+    fn styled_output_uses_sgr_sequences() {
+        let mut terminal = terminal();
+
+        terminal.print_styled_line(b"failed", Style::ERROR);
+        terminal.print_styled(b"warn", Style::WARNING.bold());
+        assert_eq!(
+            terminal.io.take_output(),
+            b"\x1b[31mfailed\x1b[0m\r\n\x1b[1;33mwarn\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn disabling_styling_strips_all_styling_codes() {
+        let mut terminal = terminal();
+        terminal.set_styling(false);
+
+        terminal.print_styled_line(b"failed", Style::ERROR);
+        assert_eq!(terminal.io.take_output(), b"failed\r\n");
+
+        let output = type_keys(&mut terminal, b"nope\r");
+        let expected = [
+            b"nope\r\nunknown command: nope (try 'help')\r\n".as_slice(),
+            PROMPT,
+        ]
+        .concat();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn plain_output_edits_with_backspaces_and_reprinted_text() {
+        let mut terminal = terminal();
+        terminal.set_plain(true);
+        terminal.io.take_output();
+
+        assert_eq!(type_keys(&mut terminal, b"abc\x1b[D\x1b[D"), b"abc\x08\x08");
+        assert_eq!(type_keys(&mut terminal, b"X"), b"Xbc\x08\x08");
+        assert_eq!(type_keys(&mut terminal, b"\x7f"), b"\x08bc \x08\x08\x08");
+        assert_eq!(type_keys(&mut terminal, b"\x05"), b"bc");
+
+        terminal.print_above(b"note");
+        assert_eq!(terminal.io.take_output(), b"\r\nnote\r\n> abc");
+        assert_eq!(type_keys(&mut terminal, b"\x0c"), b"\r\n> abc");
+
+        let output = type_keys(&mut terminal, b"\r");
+        assert_eq!(output, b"\r\nunknown command: abc (try 'help')\r\n> ");
+    }
+
+    #[test]
+    fn plain_output_sends_no_escape_sequences() {
+        let mut terminal = terminal();
+        terminal.set_plain(true);
+        terminal.set_height(Some(3));
+
+        terminal.io.type_bytes(b"count 4\r");
+        terminal.process_input();
+        terminal.io.type_bytes(b" watch\r");
+        terminal.process_input();
+        terminal.process_input();
+        terminal.request_size();
+        terminal.clear_screen();
+        terminal.show_status_bar(StatusPosition::Bottom, draw_status);
+        let output = terminal.io.take_output();
+
+        assert!(
+            !output.contains(&0x1b),
+            "{:?}",
+            String::from_utf8_lossy(&output)
+        );
+        let text = String::from_utf8_lossy(&output);
+        assert!(text.contains("--More--\r        \r3\r\n4\r\n"));
+        assert!(text.contains("full-screen views are unavailable in plain output"));
+        assert!(terminal.view.is_none() && terminal.status_bar.is_none());
+    }
+
+    #[test]
+    fn formatted_output_without_allocation() {
+        let mut terminal = terminal();
+
+        cli_println!(terminal, "CHIP_ID = 0x{:08X}", 0x1000_2927_u32);
+        cli_print!(terminal, "{} Hz", 125_000_000);
+        assert_eq!(
+            terminal.io.take_output(),
+            b"CHIP_ID = 0x10002927\r\n125000000 Hz"
+        );
+    }
+
+    #[test]
+    fn formatted_output_with_level_and_style() {
+        let mut terminal = terminal();
+        terminal.set_output_newline(OutputNewline::Lf);
+
+        cli_println!(terminal, error, "pin {} out of range", 42);
+        cli_println!(terminal, ok, "done");
+        cli_print!(terminal, style = Style::new().bold(), "{}", "x");
+        assert_eq!(
+            terminal.io.take_output(),
+            b"\x1b[31mpin 42 out of range\x1b[0m\n\x1b[32mdone\x1b[0m\n\x1b[1mx\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn pasted_text_is_inserted_as_a_unit() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"say ");
+
+        let output = type_keys(&mut terminal, b"\x1b[200~hel\x03lo\tx\x1b[201~");
+        assert_eq!(output, b"hello x");
+        assert_eq!(terminal.current_line, b"say hello x");
+        assert!(terminal.paste.is_none());
+    }
+
+    #[test]
+    fn pasted_script_runs_line_by_line() {
+        let mut terminal = terminal();
+
+        let output = type_keys(
+            &mut terminal,
+            b"\x1b[200~say hello\r\nsay help-me\r\nsay\x1b[201~",
+        );
+        let expected = [
+            b"say hello\r\nhello\x1b[0m\r\n".as_slice(),
+            &prompt(),
+            b"say help-me\r\nhelp-me\x1b[0m\r\n",
+            &prompt(),
+            b"say",
+        ]
+        .concat();
+        assert_eq!(output, expected);
+        assert_eq!(terminal.history.entry(0), Some(b"say help-me".as_slice()));
+    }
+
+    #[test]
+    fn paste_longer_than_a_line_is_reported() {
+        let mut terminal = terminal();
+
+        let pasted = [b'p'; MAX_LINE_LENGTH + 10];
+        let keys = [b"\x1b[200~".as_slice(), &pasted, b"\x1b[201~"].concat();
+        let output = type_keys(&mut terminal, &keys);
+
+        assert_eq!(terminal.current_line.len(), MAX_LINE_LENGTH);
+        let warning =
+            b"\x1b[33mpaste truncated: 10 bytes did not fit on the line (128 bytes max)\x1b[0m";
+        assert!(
+            output
+                .windows(warning.len())
+                .any(|window| window == warning)
+        );
+        assert!(output.ends_with(&pasted[..MAX_LINE_LENGTH]));
+    }
+
+    #[test]
+    fn lost_input_is_reported() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"sa");
+
+        terminal.io.dropped_input = 5;
+        terminal.process_input();
+        let expected = [
+            b"\r\x1b[0J\x1b[33minput overflow: 5 received bytes were lost\x1b[0m\r\n".as_slice(),
+            &prompt(),
+            b"sa",
+        ]
+        .concat();
+        assert_eq!(terminal.io.take_output(), expected);
+    }
 }
@@ -0,0 +1,120 @@
+//! Undo journal module for the terminal line editor.
+//!
+//! Records snapshots of the line buffer and cursor before each edit so that
+//! Ctrl-_ can step back through them and Alt-_ can reapply undone edits. Once
+//! the journal is full the oldest snapshot is discarded.
+
+use crate::constants::UNDO_DEPTH;
+use crate::peripherals::uart::history::{Line, wipe};
+use heapless::Deque;
+
+/// State of the line being edited at one point in time
+pub struct Snapshot {
+    /// Contents of the line buffer
+    pub line: Line,
+
+    /// Cursor position in the line buffer
+    pub cursor: usize,
+}
+
+impl Snapshot {
+    /// Captures the given line and cursor
+    fn new(line: &[u8], cursor: usize) -> Self {
+        Snapshot {
+            line: Line::from_slice(line).unwrap_or_default(),
+            cursor,
+        }
+    }
+}
+
+/// Bounded undo and redo stacks of line snapshots
+pub struct UndoJournal {
+    /// States to return to on undo, most recent at the back
+    undo: Deque<Snapshot, UNDO_DEPTH>,
+
+    /// States undone since the last edit, most recently undone at the back
+    redo: Deque<Snapshot, UNDO_DEPTH>,
+}
+
+impl UndoJournal {
+    /// Creates an empty journal
+    pub fn new() -> Self {
+        UndoJournal {
+            undo: Deque::new(),
+            redo: Deque::new(),
+        }
+    }
+
+    /// Records the state before an edit and forgets any undone edits
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - Line buffer before the edit
+    /// * `cursor` - Cursor position before the edit
+    pub fn record(&mut self, line: &[u8], cursor: usize) {
+        self.redo.clear();
+        push_bounded(&mut self.undo, Snapshot::new(line, cursor));
+    }
+
+    /// Steps back to the state before the most recent edit
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - Current line buffer, kept for redo
+    /// * `cursor` - Current cursor position, kept for redo
+    ///
+    /// # Returns
+    ///
+    /// The state to restore, or `None` if there is nothing to undo
+    pub fn undo(&mut self, line: &[u8], cursor: usize) -> Option<Snapshot> {
+        let snapshot = self.undo.pop_back()?;
+        push_bounded(&mut self.redo, Snapshot::new(line, cursor));
+        Some(snapshot)
+    }
+
+    /// Reapplies the most recently undone edit
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - Current line buffer, kept for undo
+    /// * `cursor` - Current cursor position, kept for undo
+    ///
+    /// # Returns
+    ///
+    /// The state to restore, or `None` if there is nothing to redo
+    pub fn redo(&mut self, line: &[u8], cursor: usize) -> Option<Snapshot> {
+        let snapshot = self.redo.pop_back()?;
+        push_bounded(&mut self.undo, Snapshot::new(line, cursor));
+        Some(snapshot)
+    }
+
+    /// Forgets all recorded states, e.g. when a new line is started
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Forgets all recorded states, overwriting every snapshot slot with zeros
+    ///
+    /// Used instead of [`Self::clear`] after editing a secret. Empty slots may still
+    /// hold snapshots popped earlier, so the stacks are filled before being wiped.
+    pub fn wipe(&mut self) {
+        for stack in [&mut self.undo, &mut self.redo] {
+            while !stack.is_full() {
+                let _ = stack.push_back(Snapshot::new(&[], 0));
+            }
+            for snapshot in stack.iter_mut() {
+                wipe(&mut snapshot.line);
+            }
+            stack.clear();
+        }
+    }
+}
+
+/// Pushes a snapshot, discarding the oldest one if the stack is full
+fn push_bounded(stack: &mut Deque<Snapshot, UNDO_DEPTH>, snapshot: Snapshot) {
+    if stack.is_full() {
+        stack.pop_front();
+    }
+    let _ = stack.push_back(snapshot);
+}
@@ -0,0 +1,241 @@
+//! UTF-8 support module for the terminal line editor.
+//!
+//! Input arrives one byte at a time, so multi-byte characters are assembled by a
+//! small decoder that rejects malformed sequences. The helpers below work on line
+//! buffers that only ever hold valid UTF-8 and translate between byte positions
+//! and the number of terminal columns the text occupies.
+
+/// Maximum number of bytes in a single UTF-8 encoded code point
+const MAX_CHAR_LEN: usize = 4;
+
+/// Code point ranges drawn two columns wide by terminal emulators
+///
+/// Covers the East Asian wide and fullwidth blocks and the common emoji blocks.
+const WIDE_RANGES: &[(u32, u32)] = &[
+    (0x1100, 0x115F),
+    (0x2E80, 0x303E),
+    (0x3041, 0x33FF),
+    (0x3400, 0x4DBF),
+    (0x4E00, 0x9FFF),
+    (0xA000, 0xA4CF),
+    (0xAC00, 0xD7A3),
+    (0xF900, 0xFAFF),
+    (0xFE30, 0xFE4F),
+    (0xFF00, 0xFF60),
+    (0xFFE0, 0xFFE6),
+    (0x1F300, 0x1F64F),
+    (0x1F900, 0x1F9FF),
+    (0x20000, 0x3FFFD),
+];
+
+/// Result of feeding one byte to the [`Utf8Decoder`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeResult {
+    /// The byte was consumed and more bytes are needed
+    Pending,
+
+    /// The byte completed a printable character, returned with its encoding
+    Complete(char, heapless::Vec<u8, MAX_CHAR_LEN>),
+
+    /// The byte does not continue a valid sequence and the sequence was dropped
+    Invalid,
+}
+
+/// Assembles multi-byte UTF-8 characters from a byte stream
+pub struct Utf8Decoder {
+    /// Bytes of the character received so far
+    buffer: heapless::Vec<u8, MAX_CHAR_LEN>,
+
+    /// Total length announced by the lead byte, or 0 when idle
+    expected: usize,
+}
+
+impl Utf8Decoder {
+    /// Creates an idle decoder
+    pub fn new() -> Self {
+        Utf8Decoder {
+            buffer: heapless::Vec::new(),
+            expected: 0,
+        }
+    }
+
+    /// Discards a partially received character
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.expected = 0;
+    }
+
+    /// Feeds a single non-ASCII byte to the decoder
+    ///
+    /// A lead byte received while a character is incomplete starts a new character.
+    /// Overlong encodings, surrogates, out-of-range code points and control
+    /// characters are rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte` - The received byte, in the range `0x80..=0xFF`
+    ///
+    /// # Returns
+    ///
+    /// Whether the byte is pending, completed a character or was rejected
+    pub fn feed(&mut self, byte: u8) -> DecodeResult {
+        let is_continuation = byte & 0xC0 == 0x80;
+        if !is_continuation {
+            self.reset();
+            self.expected = match byte {
+                0xC2..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF4 => 4,
+                _ => return DecodeResult::Invalid,
+            };
+        } else if self.expected == 0 {
+            return DecodeResult::Invalid;
+        }
+
+        let _ = self.buffer.push(byte);
+        if self.buffer.len() < self.expected {
+            return DecodeResult::Pending;
+        }
+
+        let bytes = core::mem::take(&mut self.buffer);
+        self.expected = 0;
+        match core::str::from_utf8(&bytes)
+            .ok()
+            .and_then(|text| text.chars().next())
+        {
+            Some(character) if !character.is_control() => DecodeResult::Complete(character, bytes),
+            _ => DecodeResult::Invalid,
+        }
+    }
+}
+
+/// Returns the number of terminal columns a character occupies
+pub fn char_width(character: char) -> usize {
+    let code = character as u32;
+    if WIDE_RANGES
+        .iter()
+        .any(|&(first, last)| (first..=last).contains(&code))
+    {
+        2
+    } else {
+        1
+    }
+}
+
+/// Returns the screen offset reached by printing text on rows of a fixed width
+///
+/// Offsets count cells from the start of the first row, so the row is `offset / width`
+/// and the column `offset % width`. A wide character that does not fit at the end of
+/// a row is moved to the next one, as terminals do, leaving a blank cell.
+///
+/// # Arguments
+///
+/// * `offset` - Screen offset the text starts at
+/// * `text` - Valid UTF-8 text
+/// * `width` - Number of columns of a row, at least 1
+pub fn wrapped_offset(offset: usize, text: &[u8], width: usize) -> usize {
+    let Ok(text) = core::str::from_utf8(text) else {
+        return offset + text.len();
+    };
+
+    text.chars().map(char_width).fold(offset, |offset, cells| {
+        let column = offset % width;
+        if column + cells > width {
+            offset + (width - column) + cells
+        } else {
+            offset + cells
+        }
+    })
+}
+
+/// Returns the number of characters in a UTF-8 buffer
+///
+/// # Arguments
+///
+/// * `text` - Valid UTF-8 text
+pub fn char_count(text: &[u8]) -> usize {
+    text.iter().filter(|&&byte| byte & 0xC0 != 0x80).count()
+}
+
+/// Returns whether `index` falls on a character boundary of `text`
+pub fn is_boundary(text: &[u8], index: usize) -> bool {
+    text.get(index).is_none_or(|&byte| byte & 0xC0 != 0x80)
+}
+
+/// Returns the byte position of the character before `index`
+pub fn previous_boundary(text: &[u8], index: usize) -> usize {
+    let mut position = index.saturating_sub(1);
+    while position > 0 && !is_boundary(text, position) {
+        position -= 1;
+    }
+    position
+}
+
+/// Returns the byte position of the character after `index`
+pub fn next_boundary(text: &[u8], index: usize) -> usize {
+    let mut position = (index + 1).min(text.len());
+    while !is_boundary(text, position) {
+        position += 1;
+    }
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(decoder: &mut Utf8Decoder, bytes: &[u8]) -> DecodeResult {
+        let mut result = DecodeResult::Pending;
+        for &byte in bytes {
+            result = decoder.feed(byte);
+        }
+        result
+    }
+
+    #[test]
+    fn decodes_multi_byte_characters() {
+        let mut decoder = Utf8Decoder::new();
+        for character in ['°', 'µ', '€', '漢', '🙂'] {
+            let mut encoded = [0; 4];
+            let encoded = character.encode_utf8(&mut encoded).as_bytes();
+            match decode(&mut decoder, encoded) {
+                DecodeResult::Complete(decoded, bytes) => {
+                    assert_eq!(decoded, character);
+                    assert_eq!(bytes, encoded);
+                }
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_sequences() {
+        let mut decoder = Utf8Decoder::new();
+        // Stray continuation byte, overlong encoding, surrogate, C1 control, invalid lead
+        assert_eq!(decoder.feed(0x80), DecodeResult::Invalid);
+        assert_eq!(decode(&mut decoder, &[0xC0, 0xAF]), DecodeResult::Invalid);
+        assert_eq!(
+            decode(&mut decoder, &[0xED, 0xA0, 0x80]),
+            DecodeResult::Invalid
+        );
+        assert_eq!(decode(&mut decoder, &[0xC2, 0x85]), DecodeResult::Invalid);
+        assert_eq!(decoder.feed(0xFF), DecodeResult::Invalid);
+    }
+
+    #[test]
+    fn counts_characters_and_finds_boundaries() {
+        let text = "a°漢".as_bytes();
+        assert_eq!(char_count(text), 3);
+        assert_eq!(next_boundary(text, 1), 3);
+        assert_eq!(previous_boundary(text, 6), 3);
+        assert_eq!(previous_boundary(text, 3), 1);
+    }
+
+    #[test]
+    fn wraps_wide_characters_to_the_next_row() {
+        assert_eq!(wrapped_offset(0, b"abcd", 4), 4);
+        assert_eq!(wrapped_offset(1, "a漢".as_bytes(), 4), 4);
+        // Only one cell is left on the row, so the wide character starts the next one
+        assert_eq!(wrapped_offset(3, "漢".as_bytes(), 4), 6);
+    }
+}
@@ -0,0 +1,248 @@
+//! Vi editing mode module for the terminal line editor.
+//!
+//! In vi mode, editing is modal. Typed text is inserted in insert mode, and Esc
+//! switches to normal mode, where keys are commands:
+//! - Motions: `h`/`l` (character), `w`/`b` (word), `0`/`$` (line start and end)
+//! - Edits: `x` (character under the cursor), `d` or `c` followed by a motion, `dd`
+//!   and `cc` (whole line); `c` continues in insert mode
+//! - Insertion: `i` (before the cursor), `a` (after it), `A` (at the end of the line)
+//! - History: `k` (older entry), `j` (newer entry)
+//!
+//! [`ViState`] turns the keys typed in normal mode into [`ViCommand`]s, keeping track
+//! of an operator waiting for its motion; the terminal carries the commands out.
+
+/// Editing mode of the vi line editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViMode {
+    /// Typed text is inserted into the line
+    Insert,
+
+    /// Typed keys are commands
+    Normal,
+}
+
+impl ViMode {
+    /// Returns the indicator shown in front of the prompt in this mode
+    pub fn indicator(&self) -> &'static str {
+        match self {
+            ViMode::Insert => "(ins) ",
+            ViMode::Normal => "(cmd) ",
+        }
+    }
+}
+
+/// Cursor movement of a normal-mode command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    /// One character to the left (`h`)
+    Left,
+
+    /// One character to the right (`l`)
+    Right,
+
+    /// To the start of the next word (`w`)
+    NextWordStart,
+
+    /// To the end of the current or next word, as changed by `cw`
+    WordEnd,
+
+    /// To the start of the current or previous word (`b`)
+    PreviousWordStart,
+
+    /// To the beginning of the line (`0`)
+    LineStart,
+
+    /// To the end of the line (`$`)
+    LineEnd,
+}
+
+/// Normal-mode command decoded from one or more keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViCommand {
+    /// Moves the cursor
+    Move(Motion),
+
+    /// Deletes the text between the cursor and the end of the motion
+    Delete(Motion),
+
+    /// Deletes the text between the cursor and the end of the motion, then inserts
+    Change(Motion),
+
+    /// Deletes the whole line (`dd`)
+    DeleteLine,
+
+    /// Deletes the whole line, then inserts (`cc`)
+    ChangeLine,
+
+    /// Inserts before the cursor (`i`)
+    Insert,
+
+    /// Inserts after the cursor (`a`)
+    Append,
+
+    /// Inserts at the end of the line (`A`)
+    AppendAtEnd,
+
+    /// Recalls the previous (older) history entry (`k`)
+    HistoryPrevious,
+
+    /// Recalls the next (newer) history entry (`j`)
+    HistoryNext,
+}
+
+/// Operator waiting for the motion it applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+}
+
+/// State of the vi line editor
+pub struct ViState {
+    /// Current editing mode
+    mode: ViMode,
+
+    /// Operator typed in normal mode, waiting for its motion
+    operator: Option<Operator>,
+}
+
+impl ViState {
+    /// Creates a vi editor state in insert mode
+    pub fn new() -> Self {
+        ViState {
+            mode: ViMode::Insert,
+            operator: None,
+        }
+    }
+
+    /// Returns the current editing mode
+    pub fn mode(&self) -> ViMode {
+        self.mode
+    }
+
+    /// Switches the editing mode, dropping any pending operator
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - Mode to switch to
+    pub fn set_mode(&mut self, mode: ViMode) {
+        self.mode = mode;
+        self.operator = None;
+    }
+
+    /// Feeds a key typed in normal mode
+    ///
+    /// An operator key (`d` or `c`) is remembered until the next key; a key that is not
+    /// a motion cancels it.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The typed ASCII character
+    ///
+    /// # Returns
+    ///
+    /// The completed command, or `None` if the key is pending or not a command
+    pub fn feed(&mut self, key: u8) -> Option<ViCommand> {
+        let motion = match key {
+            b'h' => Some(Motion::Left),
+            b'l' => Some(Motion::Right),
+            b'w' => Some(Motion::NextWordStart),
+            b'b' => Some(Motion::PreviousWordStart),
+            b'0' => Some(Motion::LineStart),
+            b'$' => Some(Motion::LineEnd),
+            _ => None,
+        };
+
+        match (self.operator.take(), key, motion) {
+            (Some(Operator::Delete), b'd', _) => Some(ViCommand::DeleteLine),
+            (Some(Operator::Change), b'c', _) => Some(ViCommand::ChangeLine),
+            // As in vi, `cw` leaves the separators after the word alone
+            (Some(Operator::Change), b'w', _) => Some(ViCommand::Change(Motion::WordEnd)),
+            (Some(Operator::Delete), _, Some(motion)) => Some(ViCommand::Delete(motion)),
+            (Some(Operator::Change), _, Some(motion)) => Some(ViCommand::Change(motion)),
+            (Some(_), _, None) => None,
+            (None, _, Some(motion)) => Some(ViCommand::Move(motion)),
+            (None, b'd', _) => {
+                self.operator = Some(Operator::Delete);
+                None
+            }
+            (None, b'c', _) => {
+                self.operator = Some(Operator::Change);
+                None
+            }
+            (None, b'x', _) => Some(ViCommand::Delete(Motion::Right)),
+            (None, b'i', _) => Some(ViCommand::Insert),
+            (None, b'a', _) => Some(ViCommand::Append),
+            (None, b'A', _) => Some(ViCommand::AppendAtEnd),
+            (None, b'k', _) => Some(ViCommand::HistoryPrevious),
+            (None, b'j', _) => Some(ViCommand::HistoryNext),
+            (None, _, None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn feed(keys: &[u8]) -> Vec<Option<ViCommand>> {
+        let mut vi = ViState::new();
+        vi.set_mode(ViMode::Normal);
+        keys.iter().map(|&key| vi.feed(key)).collect()
+    }
+
+    #[test]
+    fn single_keys_are_commands() {
+        assert_eq!(
+            feed(b"hw$xiAk"),
+            [
+                Some(ViCommand::Move(Motion::Left)),
+                Some(ViCommand::Move(Motion::NextWordStart)),
+                Some(ViCommand::Move(Motion::LineEnd)),
+                Some(ViCommand::Delete(Motion::Right)),
+                Some(ViCommand::Insert),
+                Some(ViCommand::AppendAtEnd),
+                Some(ViCommand::HistoryPrevious),
+            ]
+        );
+        assert_eq!(feed(b"z"), [None]);
+    }
+
+    #[test]
+    fn operators_wait_for_a_motion() {
+        assert_eq!(
+            feed(b"dwdd"),
+            [
+                None,
+                Some(ViCommand::Delete(Motion::NextWordStart)),
+                None,
+                Some(ViCommand::DeleteLine)
+            ]
+        );
+        assert_eq!(
+            feed(b"cwc0"),
+            [
+                None,
+                Some(ViCommand::Change(Motion::WordEnd)),
+                None,
+                Some(ViCommand::Change(Motion::LineStart))
+            ]
+        );
+    }
+
+    #[test]
+    fn other_keys_cancel_a_pending_operator() {
+        assert_eq!(
+            feed(b"dzh"),
+            [None, None, Some(ViCommand::Move(Motion::Left))]
+        );
+
+        let mut vi = ViState::new();
+        vi.set_mode(ViMode::Normal);
+        assert_eq!(vi.feed(b'c'), None);
+        vi.set_mode(ViMode::Insert);
+        vi.set_mode(ViMode::Normal);
+        assert_eq!(vi.feed(b'w'), Some(ViCommand::Move(Motion::NextWordStart)));
+    }
+}
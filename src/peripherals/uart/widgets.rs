@@ -0,0 +1,954 @@
+//! Widget module for interactive full-screen forms.
+//!
+//! A [`Form`] is a titled list of fields, each holding one widget:
+//! - [`Menu`]: options listed one per row, one of them selected
+//! - [`Checkbox`]: a setting that is on or off
+//! - [`NumberField`]: a number typed with digits, checked against its range
+//!
+//! Forms are edited with the keyboard: Up/Down move between fields, Left/Right change
+//! the value of the focused field, including the selection of a menu, Space toggles a
+//! checkbox, Enter submits the form and Esc cancels it. Moving between fields never
+//! changes a value. A form made of a single menu has no other field to move to, so
+//! Up/Down select its options instead. A number that is out of range keeps the focus on
+//! its field until it is corrected.
+//!
+//! Forms draw themselves into a [`Frame`]; the terminal shows them with
+//! [`Terminal::show_form`], implemented here along with the lines command handlers
+//! request with [`Terminal::request_line`].
+
+use crate::constants::MAX_FORM_FIELDS;
+use crate::peripherals::uart::ByteIo;
+use crate::peripherals::uart::escape::{Key, KeyEvent, ParseResult};
+use crate::peripherals::uart::screen::{Frame, View, ViewContent};
+use crate::peripherals::uart::terminal::{ASCIICode, Echo, Terminal};
+use core::fmt::Write;
+use core::ops::RangeInclusive;
+
+/// Maximum number of digits of a number field
+const NUMBER_DIGITS: usize = 10;
+
+/// Column the widgets start at, leaving room for the field labels
+const WIDGET_COLUMN: usize = 26;
+
+/// Row of the first field; the rows above hold the title and key help
+const FIRST_FIELD_ROW: usize = 3;
+
+/// Key help shown below the title
+const KEY_HELP: &str = "Up/Down: field  Left/Right: change  Space: toggle  Enter: save  Esc: back";
+
+/// Key pressed while a form is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormKey {
+    Up,
+    Down,
+    Left,
+    Right,
+    Space,
+    Enter,
+    Backspace,
+    Escape,
+
+    /// A decimal digit, as its ASCII character
+    Digit(u8),
+}
+
+/// Outcome of a key pressed while a form is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormEvent {
+    /// The form is still being edited
+    Editing,
+
+    /// The form was submitted with valid values
+    Submitted,
+
+    /// The form was cancelled
+    Cancelled,
+}
+
+/// Arrow-key menu of options, one of them selected
+pub struct Menu {
+    /// Options shown, one per row
+    options: &'static [&'static str],
+
+    /// Index of the selected option
+    selected: usize,
+}
+
+impl Menu {
+    /// Creates a menu
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options to show, one per row
+    /// * `selected` - Index of the option selected at first, clamped to the last one
+    pub fn new(options: &'static [&'static str], selected: usize) -> Self {
+        Menu {
+            options,
+            selected: selected.min(options.len().saturating_sub(1)),
+        }
+    }
+
+    /// Returns the index of the selected option
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Selects the option above the selected one
+    ///
+    /// # Returns
+    ///
+    /// `false` if the first option was already selected
+    pub fn select_previous(&mut self) -> bool {
+        if self.selected == 0 {
+            return false;
+        }
+        self.selected -= 1;
+        true
+    }
+
+    /// Selects the option below the selected one
+    ///
+    /// # Returns
+    ///
+    /// `false` if the last option was already selected
+    pub fn select_next(&mut self) -> bool {
+        if self.selected + 1 >= self.options.len() {
+            return false;
+        }
+        self.selected += 1;
+        true
+    }
+}
+
+/// Setting that is on or off
+pub struct Checkbox {
+    /// Whether the box is ticked
+    checked: bool,
+}
+
+impl Checkbox {
+    /// Creates a checkbox
+    pub fn new(checked: bool) -> Self {
+        Checkbox { checked }
+    }
+
+    /// Returns whether the box is ticked
+    pub fn is_checked(&self) -> bool {
+        self.checked
+    }
+
+    /// Ticks or clears the box
+    pub fn toggle(&mut self) {
+        self.checked = !self.checked;
+    }
+}
+
+/// Number typed with digits or stepped with the arrow keys, within a range
+pub struct NumberField {
+    /// Last value accepted by the field
+    value: u32,
+
+    /// Values accepted by the field
+    range: RangeInclusive<u32>,
+
+    /// Digits as typed, not yet checked against the range
+    text: heapless::String<NUMBER_DIGITS>,
+}
+
+impl NumberField {
+    /// Creates a number field
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Value shown at first, clamped to the range
+    /// * `range` - Values accepted by the field
+    pub fn new(value: u32, range: RangeInclusive<u32>) -> Self {
+        let mut field = NumberField {
+            value: value.clamp(*range.start(), *range.end()),
+            range,
+            text: heapless::String::new(),
+        };
+        field.show_value();
+        field
+    }
+
+    /// Returns the last value accepted by the field
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Returns the values accepted by the field
+    pub fn range(&self) -> &RangeInclusive<u32> {
+        &self.range
+    }
+
+    /// Returns the digits as typed
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Appends a typed digit, replacing a lone zero; digits beyond the maximum length
+    /// are ignored
+    ///
+    /// # Arguments
+    ///
+    /// * `digit` - ASCII digit
+    pub fn push_digit(&mut self, digit: u8) {
+        if self.text == "0" {
+            self.text.clear();
+        }
+        let _ = self.text.push(char::from(digit));
+    }
+
+    /// Removes the last typed digit
+    pub fn pop_digit(&mut self) {
+        self.text.pop();
+    }
+
+    /// Steps the value up or down by one, staying within the range
+    ///
+    /// A typed value is checked first; if it is invalid, stepping starts from the last
+    /// accepted value.
+    ///
+    /// # Arguments
+    ///
+    /// * `up` - Whether to increase the value
+    pub fn step(&mut self, up: bool) {
+        let _ = self.commit();
+        self.value = match up {
+            true => self.value.saturating_add(1).min(*self.range.end()),
+            false => self.value.saturating_sub(1).max(*self.range.start()),
+        };
+        self.show_value();
+    }
+
+    /// Accepts the typed digits as the value if they are within the range
+    ///
+    /// # Returns
+    ///
+    /// `false` if the digits do not form a number within the range, in which case the
+    /// value is unchanged
+    pub fn commit(&mut self) -> bool {
+        match self.text.parse() {
+            Ok(value) if self.range.contains(&value) => {
+                self.value = value;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Replaces the typed digits with the value
+    fn show_value(&mut self) {
+        self.text.clear();
+        let _ = write!(self.text, "{}", self.value);
+    }
+}
+
+/// Widget held by a form field
+pub enum Widget {
+    /// Choice of one option
+    Menu(Menu),
+
+    /// Setting that is on or off
+    Checkbox(Checkbox),
+
+    /// Number within a range
+    Number(NumberField),
+}
+
+/// Labelled widget of a form
+struct Field {
+    /// Text shown in front of the widget
+    label: &'static str,
+
+    /// The widget
+    widget: Widget,
+}
+
+impl Field {
+    /// Returns the number of rows the field takes up
+    fn rows(&self) -> usize {
+        match &self.widget {
+            Widget::Menu(menu) => menu.options.len().max(1),
+            _ => 1,
+        }
+    }
+}
+
+/// Titled list of fields edited with the keyboard
+pub struct Form {
+    /// Title shown on the first row
+    title: &'static str,
+
+    /// Number of the item the form applies to, e.g. a pin, shown after the title
+    context: Option<usize>,
+
+    /// Fields, in the order they are shown
+    fields: heapless::Vec<Field, MAX_FORM_FIELDS>,
+
+    /// Index of the field keys apply to
+    focus: usize,
+
+    /// Whether the focused number field holds a value out of its range
+    invalid: bool,
+}
+
+impl Form {
+    /// Creates a form without fields
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - Title shown on the first row
+    pub fn new(title: &'static str) -> Self {
+        Form {
+            title,
+            context: None,
+            fields: heapless::Vec::new(),
+            focus: 0,
+            invalid: false,
+        }
+    }
+
+    /// Adds a field; fields beyond [`MAX_FORM_FIELDS`] are dropped
+    ///
+    /// The field is referred to by its position, counted from 0 in the order the fields
+    /// were added.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Text shown in front of the widget
+    /// * `widget` - The widget
+    pub fn field(mut self, label: &'static str, widget: Widget) -> Self {
+        let _ = self.fields.push(Field { label, widget });
+        self
+    }
+
+    /// Sets the number of the item the form applies to, e.g. a pin
+    ///
+    /// The number is shown after the title and handed back to the form handler with
+    /// the form, so the handler knows what to apply the fields to.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - Number of the item
+    pub fn with_context(mut self, context: usize) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Returns the number of the item the form applies to, if one was set with
+    /// [`Self::with_context`]
+    pub fn context(&self) -> Option<usize> {
+        self.context
+    }
+
+    /// Returns the index of the option selected in a menu field
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - Position of the field
+    ///
+    /// # Returns
+    ///
+    /// The selected option, or `None` if the field is not a menu
+    pub fn selected(&self, field: usize) -> Option<usize> {
+        match &self.fields.get(field)?.widget {
+            Widget::Menu(menu) => Some(menu.selected()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether a checkbox field is ticked
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - Position of the field
+    ///
+    /// # Returns
+    ///
+    /// The state of the box, or `None` if the field is not a checkbox
+    pub fn checked(&self, field: usize) -> Option<bool> {
+        match &self.fields.get(field)?.widget {
+            Widget::Checkbox(checkbox) => Some(checkbox.is_checked()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a number field
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - Position of the field
+    ///
+    /// # Returns
+    ///
+    /// The last value accepted by the field, or `None` if the field is not a number
+    pub fn number(&self, field: usize) -> Option<u32> {
+        match &self.fields.get(field)?.widget {
+            Widget::Number(number) => Some(number.value()),
+            _ => None,
+        }
+    }
+
+    /// Applies a key to the form
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The pressed key
+    ///
+    /// # Returns
+    ///
+    /// Whether the form is still being edited, was submitted or was cancelled
+    pub fn handle(&mut self, key: FormKey) -> FormEvent {
+        self.invalid = false;
+        match key {
+            FormKey::Escape => return FormEvent::Cancelled,
+            FormKey::Enter if self.commit_focused() => return FormEvent::Submitted,
+            FormKey::Up => self.move_focus(false),
+            FormKey::Down => self.move_focus(true),
+            _ => {}
+        }
+
+        let Some(field) = self.fields.get_mut(self.focus) else {
+            return FormEvent::Editing;
+        };
+        match (key, &mut field.widget) {
+            (FormKey::Left, Widget::Menu(menu)) => {
+                menu.select_previous();
+            }
+            (FormKey::Right, Widget::Menu(menu)) => {
+                menu.select_next();
+            }
+            (FormKey::Left | FormKey::Right | FormKey::Space, Widget::Checkbox(checkbox)) => {
+                checkbox.toggle()
+            }
+            (FormKey::Left, Widget::Number(number)) => number.step(false),
+            (FormKey::Right, Widget::Number(number)) => number.step(true),
+            (FormKey::Digit(digit), Widget::Number(number)) => number.push_digit(digit),
+            (FormKey::Backspace, Widget::Number(number)) => number.pop_digit(),
+            _ => {}
+        }
+        FormEvent::Editing
+    }
+
+    /// Moves the focus one field up or down
+    ///
+    /// The focus stays on a number field that is out of range. In a form made of a
+    /// single menu, the selection of the menu moves instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `down` - Whether to move down
+    fn move_focus(&mut self, down: bool) {
+        if let [
+            Field {
+                widget: Widget::Menu(menu),
+                ..
+            },
+        ] = self.fields.as_mut_slice()
+        {
+            match down {
+                true => menu.select_next(),
+                false => menu.select_previous(),
+            };
+            return;
+        }
+
+        if !self.commit_focused() {
+            return;
+        }
+        if down {
+            self.focus = (self.focus + 1).min(self.fields.len().saturating_sub(1));
+        } else {
+            self.focus = self.focus.saturating_sub(1);
+        }
+    }
+
+    /// Accepts the typed value of the focused field if it is a number
+    ///
+    /// # Returns
+    ///
+    /// `false` if the number is out of range, which is then reported below the fields
+    fn commit_focused(&mut self) -> bool {
+        if let Some(Field {
+            widget: Widget::Number(number),
+            ..
+        }) = self.fields.get_mut(self.focus)
+        {
+            self.invalid = !number.commit();
+        }
+        !self.invalid
+    }
+
+    /// Draws the form
+    ///
+    /// The focused field is marked with `>` in the first column, on the row of the
+    /// selected option for menus.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Blank frame to draw into
+    pub fn render(&self, frame: &mut Frame) {
+        let _ = match self.context {
+            Some(context) => write!(frame.at(0, 0), "{} {}", self.title, context),
+            None => frame.at(0, 0).write_str(self.title),
+        };
+        let _ = frame.at(1, 0).write_str(KEY_HELP);
+
+        let mut row = FIRST_FIELD_ROW;
+        for (index, field) in self.fields.iter().enumerate() {
+            let _ = write!(frame.at(row, 2), "{}", field.label);
+            let focused = index == self.focus;
+
+            match &field.widget {
+                Widget::Menu(menu) => {
+                    for (offset, option) in menu.options.iter().enumerate() {
+                        let selected = offset == menu.selected;
+                        let mark = if selected { '*' } else { ' ' };
+                        let _ = write!(frame.at(row + offset, WIDGET_COLUMN), "({mark}) {option}");
+                        if focused && selected {
+                            let _ = frame.at(row + offset, 0).write_str(">");
+                        }
+                    }
+                }
+                Widget::Checkbox(checkbox) => {
+                    let mark = if checkbox.is_checked() { 'x' } else { ' ' };
+                    let _ = write!(frame.at(row, WIDGET_COLUMN), "[{mark}]");
+                }
+                Widget::Number(number) => {
+                    let _ = write!(
+                        frame.at(row, WIDGET_COLUMN),
+                        "[{:>width$}]",
+                        number.text(),
+                        width = NUMBER_DIGITS
+                    );
+                }
+            }
+            if focused && !matches!(field.widget, Widget::Menu(_)) {
+                let _ = frame.at(row, 0).write_str(">");
+            }
+            row += field.rows();
+        }
+
+        if self.invalid
+            && let Some(Field {
+                label,
+                widget: Widget::Number(number),
+            }) = self.fields.get(self.focus)
+        {
+            let _ = write!(
+                frame.at(row + 1, 0),
+                "{}: enter a number from {} to {}",
+                label,
+                number.range().start(),
+                number.range().end()
+            );
+        }
+    }
+}
+
+/// Line of input requested by a command handler
+pub(super) struct LineRequest<IO: ByteIo> {
+    /// Prompt displayed instead of the CLI prompt
+    pub(super) prompt: &'static [u8],
+
+    /// How typed characters are displayed
+    pub(super) echo: Echo,
+
+    /// Function receiving the line
+    pub(super) handler: LineHandler<IO>,
+}
+
+/// Signature of a function receiving a line requested with [`Terminal::request_line`]
+///
+/// The handler receives the terminal and the submitted line, or `None` if the user
+/// abandoned it with Ctrl-C. It may request another line.
+pub type LineHandler<IO> = fn(terminal: &mut Terminal<IO>, line: Option<&str>);
+
+/// Signature of a function receiving a form shown with [`Terminal::show_form`]
+///
+/// The handler receives the terminal and the submitted form, or `None` if the user
+/// cancelled it. It may show another form.
+pub type FormHandler<IO> = fn(terminal: &mut Terminal<IO>, form: Option<&Form>);
+
+impl<IO: ByteIo> Terminal<IO> {
+    /// Requests the next line typed by the user
+    ///
+    /// Intended for command handlers that need further input, e.g. a password. The
+    /// handler returns at once; once it has, the given prompt is shown instead of the
+    /// CLI prompt, and the next submitted line is passed to `handler` from a later
+    /// [`Self::process_input`] call instead of being run as a command. Requested lines
+    /// are not added to the history, and history recall, search and completion are
+    /// unavailable while one is edited.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - Prompt to display for the line
+    /// * `echo` - How typed characters are displayed; text typed with [`Echo::Masked`] or
+    ///   [`Echo::Hidden`] is also kept out of the kill ring, and overwritten in memory
+    ///   once `handler` returns
+    /// * `handler` - Function receiving the line
+    #[allow(unused)]
+    pub fn request_line(&mut self, prompt: &'static [u8], echo: Echo, handler: LineHandler<IO>) {
+        self.line_request = Some(LineRequest {
+            prompt,
+            echo,
+            handler,
+        });
+    }
+
+    /// Returns how the line being edited is displayed
+    pub(super) fn echo(&self) -> Echo {
+        self.line_request
+            .as_ref()
+            .map_or(Echo::Normal, |request| request.echo)
+    }
+
+    /// Replaces the prompt with a full-screen form until it is submitted or cancelled
+    ///
+    /// Intended for command handlers. The form is edited with the keys described in
+    /// [`crate::peripherals::uart::widgets`] and redrawn after every key, sending only the
+    /// cells that changed. Enter passes the form to `handler` and Esc or Ctrl-C passes
+    /// `None`; unless the handler shows another form, the screen is then cleared and the
+    /// prompt shown again. Plain output cannot show forms, so `handler` receives `None`
+    /// at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `form` - Form to edit
+    /// * `handler` - Function receiving the form once finished
+    pub fn show_form(&mut self, form: Form, handler: FormHandler<IO>) {
+        if self.plain {
+            self.report_plain("full-screen views");
+            handler(self, None);
+            return;
+        }
+        self.open_view(ViewContent::Form(form, handler));
+    }
+
+    /// Returns whether a form is shown
+    pub(super) fn is_form_shown(&self) -> bool {
+        self.view
+            .as_ref()
+            .is_some_and(|view| matches!(view.content, ViewContent::Form(..)))
+    }
+
+    /// Decodes received bytes into keys for the form shown
+    ///
+    /// Bytes received after the form is finished are kept for the prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Received bytes
+    pub(super) fn process_form_bytes(&mut self, bytes: &[u8]) {
+        for (index, &data) in bytes.iter().enumerate() {
+            if !self.is_form_shown() {
+                let _ = self.held_input.extend_from_slice(&bytes[index..]);
+                return;
+            }
+            if data != ASCIICode::CarriageReturn as u8 && data != ASCIICode::Newline as u8 {
+                self.pending_line_ending = None;
+            }
+
+            let escape_pending = self.escape_parser.is_escape_pending();
+            let key = match self.escape_parser.feed(data) {
+                ParseResult::Pending | ParseResult::Discarded => None,
+                ParseResult::Complete(sequence) => {
+                    KeyEvent::from_sequence(&sequence).and_then(|event| match event.key {
+                        Key::Up => Some(FormKey::Up),
+                        Key::Down => Some(FormKey::Down),
+                        Key::Left => Some(FormKey::Left),
+                        Key::Right => Some(FormKey::Right),
+                        // Esc typed just before another key
+                        Key::Meta(_) => Some(FormKey::Escape),
+                        _ => None,
+                    })
+                }
+                ParseResult::Rejected => {
+                    if escape_pending {
+                        // An ESC followed by a control character was the Esc key
+                        self.process_form_key(FormKey::Escape);
+                        if !self.is_form_shown() {
+                            let _ = self.held_input.extend_from_slice(&bytes[index..]);
+                            return;
+                        }
+                    }
+                    self.form_key(data)
+                }
+            };
+
+            if let Some(key) = key {
+                self.process_form_key(key);
+            }
+        }
+    }
+
+    /// Maps a received byte to a form key
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Received byte that is not part of an escape sequence
+    fn form_key(&mut self, data: u8) -> Option<FormKey> {
+        match data {
+            x if x == ASCIICode::CarriageReturn as u8 || x == ASCIICode::Newline as u8 => {
+                self.accept_line_ending(data).then_some(FormKey::Enter)
+            }
+            x if x == ASCIICode::Space as u8 => Some(FormKey::Space),
+            x if x == ASCIICode::Tab as u8 => Some(FormKey::Down),
+            x if x == ASCIICode::Backspace as u8 || x == ASCIICode::Delete as u8 => {
+                Some(FormKey::Backspace)
+            }
+            x if x == ASCIICode::CtrlC as u8 => Some(FormKey::Escape),
+            b'0'..=b'9' => Some(FormKey::Digit(data)),
+            _ => None,
+        }
+    }
+
+    /// Applies a key to the form shown, passing the form to its handler once finished
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The pressed key
+    pub(super) fn process_form_key(&mut self, key: FormKey) {
+        let Some(View {
+            content: ViewContent::Form(form, _),
+            ..
+        }) = &mut self.view
+        else {
+            return;
+        };
+
+        let event = form.handle(key);
+        if event == FormEvent::Editing {
+            self.redraw_view();
+            return;
+        }
+
+        let Some(View {
+            content: ViewContent::Form(form, handler),
+            ..
+        }) = self.view.take()
+        else {
+            return;
+        };
+        self.leave_view();
+        handler(self, (event == FormEvent::Submitted).then_some(&form));
+        if self.view.is_none() {
+            self.print_prompt();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::uart::fake::{prompt, terminal, type_keys};
+
+    fn form() -> Form {
+        Form::new("Settings")
+            .field("Mode", Widget::Menu(Menu::new(&["a", "b"], 0)))
+            .field("Enabled", Widget::Checkbox(Checkbox::new(false)))
+            .field("Rows", Widget::Number(NumberField::new(24, 2..=200)))
+    }
+
+    fn row_text(frame: &Frame, row: usize) -> &str {
+        core::str::from_utf8(frame.row(row)).unwrap().trim_end()
+    }
+
+    #[test]
+    fn menus_select_with_arrow_keys() {
+        let mut menu = Menu::new(&["a", "b", "c"], 5);
+        assert_eq!(menu.selected(), 2);
+        assert!(!menu.select_next());
+        assert!(menu.select_previous());
+        assert_eq!(menu.selected(), 1);
+    }
+
+    #[test]
+    fn number_fields_check_their_range() {
+        let mut number = NumberField::new(500, 2..=200);
+        assert_eq!(number.value(), 200);
+
+        number.pop_digit();
+        number.pop_digit();
+        number.pop_digit();
+        number.push_digit(b'0');
+        number.push_digit(b'1');
+        assert_eq!(number.text(), "1");
+        assert!(!number.commit());
+        assert_eq!(number.value(), 200);
+
+        // Stepping starts from the last accepted value and stays in range
+        number.step(true);
+        assert_eq!(number.text(), "200");
+        number.step(false);
+        assert_eq!(number.value(), 199);
+    }
+
+    #[test]
+    fn keys_move_between_and_edit_fields() {
+        let mut form = form();
+
+        // Moving across the menu leaves its selection alone
+        assert_eq!(form.handle(FormKey::Down), FormEvent::Editing);
+        assert_eq!(form.selected(0), Some(0));
+        form.handle(FormKey::Up);
+        form.handle(FormKey::Right);
+        assert_eq!(form.selected(0), Some(1));
+        form.handle(FormKey::Down);
+        form.handle(FormKey::Up);
+        assert_eq!(form.selected(0), Some(1));
+
+        form.handle(FormKey::Down);
+        form.handle(FormKey::Space);
+        assert_eq!(form.checked(1), Some(true));
+
+        form.handle(FormKey::Down);
+        form.handle(FormKey::Backspace);
+        form.handle(FormKey::Backspace);
+        form.handle(FormKey::Digit(b'1'));
+        assert_eq!(form.handle(FormKey::Enter), FormEvent::Editing);
+        assert_eq!(form.number(2), Some(24));
+
+        form.handle(FormKey::Digit(b'6'));
+        assert_eq!(form.handle(FormKey::Enter), FormEvent::Submitted);
+        assert_eq!(form.number(2), Some(16));
+        assert_eq!(form.number(0), None);
+        assert_eq!(form.handle(FormKey::Escape), FormEvent::Cancelled);
+    }
+
+    #[test]
+    fn single_menu_forms_select_with_up_and_down() {
+        let mut form = Form::new("Menu").field("Pick", Widget::Menu(Menu::new(&["a", "b"], 0)));
+        form.handle(FormKey::Down);
+        assert_eq!(form.selected(0), Some(1));
+        form.handle(FormKey::Up);
+        assert_eq!(form.selected(0), Some(0));
+    }
+
+    #[test]
+    fn renders_fields_focus_and_errors() {
+        let mut form = form();
+        form.handle(FormKey::Right);
+        form.handle(FormKey::Down);
+        form.handle(FormKey::Down);
+        form.handle(FormKey::Backspace);
+        form.handle(FormKey::Backspace);
+        form.handle(FormKey::Enter);
+
+        let mut frame = Frame::new();
+        form.render(&mut frame);
+        assert_eq!(row_text(&frame, 0), "Settings");
+        assert_eq!(row_text(&frame, 3), "  Mode                    ( ) a");
+        assert_eq!(row_text(&frame, 4), "                          (*) b");
+        assert_eq!(row_text(&frame, 5), "  Enabled                 [ ]");
+        assert_eq!(
+            row_text(&frame, 6),
+            "> Rows                    [          ]"
+        );
+        assert_eq!(row_text(&frame, 8), "Rows: enter a number from 2 to 200");
+
+        // The context follows the title
+        let mut frame = Frame::new();
+        form.with_context(7).render(&mut frame);
+        assert_eq!(row_text(&frame, 0), "Settings 7");
+    }
+
+    #[test]
+    fn forms_are_edited_with_keys_and_passed_to_their_handler() {
+        let mut terminal = terminal();
+        let output = type_keys(&mut terminal, b"pick\r");
+        assert!(output.ends_with(
+            b"\x1b[4;1H>\x1b[4;3HColour\x1b[4;27H(*)\x1b[4;31Hred\x1b[5;27H(\x1b[5;29H)\
+              \x1b[5;31Hgreen\x1b[6;3HCount\x1b[6;27H[\x1b[6;37H5]"
+        ));
+        terminal.io.take_output();
+
+        // Only the cells that changed are redrawn
+        terminal.io.type_bytes(b"\x1b[C");
+        terminal.process_input();
+        assert_eq!(
+            terminal.io.take_output(),
+            b"\x1b[4;1H \x1b[4;28H \x1b[5;1H>\x1b[5;28H*"
+        );
+
+        // Input following the submitting Enter is kept for the prompt
+        terminal.io.type_bytes(b"\t\x7f7\rsay");
+        terminal.process_input();
+        let output = terminal.io.take_output();
+        let expected = [b"picked 1 7\r\n".as_slice(), &prompt()].concat();
+        assert!(output.ends_with(&expected));
+        terminal.process_input();
+        assert_eq!(terminal.current_line, b"say");
+    }
+
+    #[test]
+    fn forms_are_cancelled_with_esc() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"pick\r");
+        terminal.io.take_output();
+
+        // Without a timer, a lone ESC is the Esc key
+        terminal.io.type_bytes(b"\x1b");
+        terminal.process_input();
+        let expected = [b"\x1b[2J\x1b[H\x1b[?25hcancelled\r\n".as_slice(), &prompt()].concat();
+        assert_eq!(terminal.io.take_output(), expected);
+    }
+
+    #[test]
+    fn masked_line_is_passed_to_the_requesting_handler() {
+        let mut terminal = terminal();
+        assert_eq!(
+            type_keys(&mut terminal, b"login\r"),
+            b"login\r\n\x1b[34mPassword: \x1b[0m"
+        );
+        // Killed text is not kept for yanking
+        type_keys(&mut terminal, b"oops\x15");
+
+        assert_eq!(type_keys(&mut terminal, b"hunter2"), b"*******");
+        assert_eq!(type_keys(&mut terminal, b"\x1b[D"), b"\x1b[D");
+
+        let expected = [b"\x1b[C\r\ngot hunter2\r\n".as_slice(), &prompt()].concat();
+        assert_eq!(type_keys(&mut terminal, b"\r"), expected);
+
+        // The secret stays out of the history and the kill ring
+        assert_eq!(terminal.history.entry(0), Some(b"login".as_slice()));
+        assert_eq!(terminal.history.entry(1), None);
+        assert_eq!(terminal.kill_ring.get(0), None);
+
+        // Nor does it linger in the line buffer or the undo journal
+        let line = &terminal.current_line;
+        // SAFETY: wiping initialized the whole buffer
+        let buffer = unsafe { core::slice::from_raw_parts(line.as_ptr(), line.capacity()) };
+        assert!(buffer.iter().all(|&byte| byte == 0));
+        assert!(terminal.journal.undo(b"", 0).is_none());
+    }
+
+    #[test]
+    fn hidden_line_echoes_nothing() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"key\r");
+
+        assert_eq!(type_keys(&mut terminal, b"s3cret\x1b[D\x1b[C\x17"), b"");
+        // History and completion are not available for requested lines
+        assert_eq!(type_keys(&mut terminal, b"\x1b[A\t\x12"), b"");
+        assert_eq!(terminal.kill_ring.get(0), None);
+
+        let output = type_keys(&mut terminal, b"abc\r");
+        assert!(output.starts_with(b"\r\ngot abc\r\n"));
+    }
+
+    #[test]
+    fn ctrl_c_abandons_a_requested_line() {
+        let mut terminal = terminal();
+        type_keys(&mut terminal, b"login\r");
+
+        let expected = [b"**^C\x1b[0m\r\ncancelled\r\n".as_slice(), &prompt()].concat();
+        assert_eq!(type_keys(&mut terminal, b"ab\x03"), expected);
+
+        // The next line is a command again
+        type_keys(&mut terminal, b"say hello\r");
+        assert_eq!(terminal.history.entry(0), Some(b"say hello".as_slice()));
+    }
+}
@@ -0,0 +1,69 @@
+//! Simulated clock management module for the host simulator.
+//!
+//! Stands in for the RP2040 clock setup so the shell can be built without the
+//! PAC-backed clock peripherals.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Peripheral clock frequency reported by the simulator, matching the firmware configuration
+const SIM_PERIPHERAL_CLOCK_FREQ: u32 = 125_000_000;
+
+/// Clock frequencies reported by the simulator, matching the firmware configuration
+const SIM_FREQUENCIES: ClockFrequencies = ClockFrequencies {
+    system: 125_000_000,
+    reference: 12_000_000,
+    peripheral: SIM_PERIPHERAL_CLOCK_FREQ,
+    usb: 48_000_000,
+    adc: 48_000_000,
+    rtc: 46_875,
+};
+
+/// Whether [`ClockAPI::publish`] has been called
+static PUBLISHED: AtomicBool = AtomicBool::new(false);
+
+/// Frequencies in Hz of the simulated clocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockFrequencies {
+    /// System clock
+    pub system: u32,
+
+    /// Reference clock
+    pub reference: u32,
+
+    /// Peripheral clock
+    pub peripheral: u32,
+
+    /// USB clock
+    pub usb: u32,
+
+    /// ADC clock
+    pub adc: u32,
+
+    /// RTC clock
+    pub rtc: u32,
+}
+
+/// Returns the simulated clock frequencies once published by [`ClockAPI::publish`]
+pub fn frequencies() -> Option<ClockFrequencies> {
+    PUBLISHED.load(Ordering::Relaxed).then_some(SIM_FREQUENCIES)
+}
+
+/// Simulated replacement for the RP2040 clock manager
+pub struct ClockAPI;
+
+impl ClockAPI {
+    /// Creates a new simulated clock manager
+    pub fn new() -> Self {
+        ClockAPI
+    }
+
+    /// Returns the simulated peripheral clock frequency in Hz
+    pub fn uart_clock_freq(&self) -> u32 {
+        SIM_PERIPHERAL_CLOCK_FREQ
+    }
+
+    /// Publishes the simulated clock frequencies so they can be read through [`frequencies`]
+    pub fn publish(&self) {
+        PUBLISHED.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,233 @@
+//! Simulated serial console for the host simulator.
+//!
+//! Implements [`ByteIo`] on top of the controlling terminal and a pseudo-terminal:
+//! - stdin is switched to raw mode so every keystroke reaches the line editor
+//! - a pty is opened so host tools can connect to the shell as if it were a serial port
+//!
+//! Output is mirrored to stdout and the pty; input is accepted from both.
+//! Pressing Ctrl-] on stdin restores the terminal and quits the simulator.
+
+use crate::constants::RX_BUFFER_SIZE;
+use crate::peripherals::uart::ByteIo;
+use std::ffi::CStr;
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::process;
+
+/// Key that quits the simulator when typed on stdin (Ctrl-])
+const QUIT_KEY: u8 = 0x1D;
+
+/// Milliseconds to wait for input before returning to the main loop
+const POLL_TIMEOUT_MS: i32 = 10;
+
+/// Host console implementing the terminal transport
+pub struct SimConsole {
+    /// Terminal settings of stdin before raw mode was enabled, restored on exit
+    original_termios: Option<libc::termios>,
+
+    /// Whether stdin can still deliver input
+    stdin_open: bool,
+
+    /// Master side of the pseudo-terminal
+    pty_master: Option<OwnedFd>,
+
+    /// Slave side of the pseudo-terminal, kept open so the master never sees a hangup
+    _pty_slave: Option<OwnedFd>,
+
+    /// Path host tools can open to connect to the shell
+    pty_path: Option<String>,
+
+    /// Output written since the last flush
+    pending_output: Vec<u8>,
+}
+
+impl SimConsole {
+    /// Opens the console, switching stdin to raw mode and creating the pty
+    ///
+    /// Either side is optional: the console keeps working without a tty on stdin
+    /// or without pty support on the host.
+    pub fn open() -> Self {
+        let original_termios = enable_raw_mode(libc::STDIN_FILENO);
+        let (pty_master, pty_slave, pty_path) = match open_pty() {
+            Some((master, slave, path)) => (Some(master), Some(slave), Some(path)),
+            None => (None, None, None),
+        };
+
+        SimConsole {
+            original_termios,
+            stdin_open: true,
+            pty_master,
+            _pty_slave: pty_slave,
+            pty_path,
+            pending_output: Vec::new(),
+        }
+    }
+
+    /// Returns the path of the pseudo-terminal slave, if one was created
+    pub fn pty_path(&self) -> Option<&str> {
+        self.pty_path.as_deref()
+    }
+
+    /// Writes buffered output to stdout and the pty
+    fn flush(&mut self) {
+        if self.pending_output.is_empty() {
+            return;
+        }
+
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(&self.pending_output);
+        let _ = stdout.flush();
+
+        if let Some(master) = &self.pty_master {
+            // Output is dropped while nobody drains the pty
+            write_all(master.as_raw_fd(), &self.pending_output);
+        }
+
+        self.pending_output.clear();
+    }
+
+    /// Restores stdin and exits the simulator
+    fn quit(&mut self) -> ! {
+        // Leave bracketed paste mode, which the terminal enabled on startup
+        self.print(b"\x1b[?2004l");
+        self.flush();
+        if let Some(termios) = &self.original_termios {
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios) };
+        }
+        println!();
+        process::exit(0);
+    }
+}
+
+impl ByteIo for SimConsole {
+    fn putc(&mut self, c: u8) {
+        self.pending_output.push(c);
+    }
+
+    fn print(&mut self, s: &[u8]) {
+        self.pending_output.extend_from_slice(s);
+    }
+
+    fn get_input(&mut self) -> heapless::Vec<u8, RX_BUFFER_SIZE> {
+        self.flush();
+
+        let mut fds = [
+            libc::pollfd {
+                fd: if self.stdin_open {
+                    libc::STDIN_FILENO
+                } else {
+                    -1
+                },
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: self.pty_master.as_ref().map_or(-1, |fd| fd.as_raw_fd()),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, POLL_TIMEOUT_MS) };
+
+        let mut buffer = heapless::Vec::new();
+        let mut chunk = [0u8; RX_BUFFER_SIZE];
+
+        if fds[0].revents & (libc::POLLIN | libc::POLLHUP) != 0 {
+            match read(libc::STDIN_FILENO, &mut chunk) {
+                Some(0) | None => self.stdin_open = false,
+                Some(count) => {
+                    if chunk[..count].contains(&QUIT_KEY) {
+                        self.quit();
+                    }
+                    let _ = buffer.extend_from_slice(&chunk[..count]);
+                }
+            }
+        }
+
+        if fds[1].revents & libc::POLLIN != 0 {
+            let space = buffer.capacity() - buffer.len();
+            if let Some(count) = read(fds[1].fd, &mut chunk[..space]) {
+                let _ = buffer.extend_from_slice(&chunk[..count]);
+            }
+        }
+
+        buffer
+    }
+}
+
+/// Switches a terminal to raw mode
+///
+/// # Returns
+///
+/// The previous settings, or `None` if the descriptor is not a terminal
+fn enable_raw_mode(fd: RawFd) -> Option<libc::termios> {
+    unsafe {
+        let mut termios: libc::termios = core::mem::zeroed();
+        if libc::tcgetattr(fd, &mut termios) != 0 {
+            return None;
+        }
+
+        let original = termios;
+        libc::cfmakeraw(&mut termios);
+        libc::tcsetattr(fd, libc::TCSANOW, &termios);
+        Some(original)
+    }
+}
+
+/// Creates a pseudo-terminal with a raw-mode slave
+///
+/// # Returns
+///
+/// The non-blocking master, the slave and the slave path, or `None` on failure
+fn open_pty() -> Option<(OwnedFd, OwnedFd, String)> {
+    unsafe {
+        let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        if master < 0 {
+            return None;
+        }
+        let master = OwnedFd::from_raw_fd(master);
+
+        if libc::grantpt(master.as_raw_fd()) != 0 || libc::unlockpt(master.as_raw_fd()) != 0 {
+            return None;
+        }
+
+        let mut name = [0 as libc::c_char; 64];
+        if libc::ptsname_r(master.as_raw_fd(), name.as_mut_ptr(), name.len()) != 0 {
+            return None;
+        }
+        let path = CStr::from_ptr(name.as_ptr()).to_str().ok()?.to_owned();
+
+        let slave = libc::open(name.as_ptr(), libc::O_RDWR | libc::O_NOCTTY);
+        if slave < 0 {
+            return None;
+        }
+        let slave = OwnedFd::from_raw_fd(slave);
+        enable_raw_mode(slave.as_raw_fd());
+
+        let flags = libc::fcntl(master.as_raw_fd(), libc::F_GETFL);
+        libc::fcntl(master.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK);
+
+        Some((master, slave, path))
+    }
+}
+
+/// Reads available bytes from a descriptor
+///
+/// # Returns
+///
+/// The number of bytes read (0 at end of file), or `None` on error
+fn read(fd: RawFd, buffer: &mut [u8]) -> Option<usize> {
+    let count = unsafe { libc::read(fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+    usize::try_from(count).ok()
+}
+
+/// Writes a buffer to a descriptor, giving up on the first error
+fn write_all(fd: RawFd, mut buffer: &[u8]) {
+    while !buffer.is_empty() {
+        let count = unsafe { libc::write(fd, buffer.as_ptr().cast(), buffer.len()) };
+        match usize::try_from(count) {
+            Ok(count) if count > 0 => buffer = &buffer[count..],
+            _ => return,
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Simulated GPIO module for the host simulator.
+//!
+//! Mirrors the API of the RP2040 GPIO module but keeps the pin state in memory.
+//! After every change the full pin table is written to a state file so the
+//! simulated board can be inspected from the host, e.g. with `watch cat <file>`.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+/// Maximum number of GPIO pins available on the RP2040
+pub const NUM_PINS: usize = 30;
+
+/// Function select value routing a pin to the SIO block
+const SIO_FUNCTION: u8 = 5;
+
+/// Global GPIO instance shared with the CLI command handlers
+static GPIO: Mutex<Option<Gpio>> = Mutex::new(None);
+
+/// Runs a closure with exclusive access to the global GPIO instance
+///
+/// # Arguments
+///
+/// * `f` - Closure receiving the installed `Gpio`
+///
+/// # Returns
+///
+/// The closure result, or `None` if no `Gpio` has been installed yet
+pub fn with_gpio<R>(f: impl FnOnce(&mut Gpio) -> R) -> Option<R> {
+    let mut gpio = GPIO.lock().unwrap_or_else(PoisonError::into_inner);
+    gpio.as_mut().map(f)
+}
+
+/// Simulated GPIO bank
+pub struct Gpio {
+    /// Output latch, one bit per pin
+    output_levels: u32,
+
+    /// Output enable, one bit per pin
+    output_enable: u32,
+
+    /// Levels seen on pins configured as inputs, one bit per pin
+    input_levels: u32,
+
+    /// Function selected for each pin
+    functions: [u8; NUM_PINS],
+
+    /// File the pin table is published to after every change
+    state_path: Option<PathBuf>,
+}
+
+impl Gpio {
+    /// Creates a simulated GPIO bank with every pin as a low SIO input
+    ///
+    /// # Arguments
+    ///
+    /// * `state_path` - File to publish the pin table to, if any
+    pub fn new(state_path: Option<PathBuf>) -> Self {
+        let gpio = Gpio {
+            output_levels: 0,
+            output_enable: 0,
+            input_levels: 0,
+            functions: [SIO_FUNCTION; NUM_PINS],
+            state_path,
+        };
+        gpio.publish();
+        gpio
+    }
+
+    /// Moves this instance into global storage so it can be reached through [`with_gpio`]
+    pub fn install(self) {
+        *GPIO.lock().unwrap_or_else(PoisonError::into_inner) = Some(self);
+    }
+
+    /// Sets a GPIO pin to high state.
+    pub fn set_high(&mut self, pin_num: usize) {
+        self.output_levels |= 1 << pin_num;
+        self.publish();
+    }
+
+    /// Sets a GPIO pin to low state.
+    pub fn set_low(&mut self, pin_num: usize) {
+        self.output_levels &= !(1 << pin_num);
+        self.publish();
+    }
+
+    /// Configures a GPIO pin as an output.
+    pub fn set_output(&mut self, pin_num: usize) {
+        self.output_enable |= 1 << pin_num;
+        self.publish();
+    }
+
+    /// Configures a GPIO pin as an input.
+    pub fn set_input(&mut self, pin_num: usize) {
+        self.output_enable &= !(1 << pin_num);
+        self.publish();
+    }
+
+    /// Reads the current state of a GPIO pin.
+    ///
+    /// Output pins read back their latch; input pins read the simulated input level.
+    pub fn read(&mut self, pin_num: usize) -> bool {
+        self.read_level(pin_num)
+    }
+
+    /// Returns the function selected for a GPIO pin.
+    pub fn function(&self, pin_num: usize) -> u8 {
+        self.functions[pin_num]
+    }
+
+    /// Returns whether a GPIO pin is configured as an output.
+    pub fn is_output(&self, pin_num: usize) -> bool {
+        self.output_enable & (1 << pin_num) != 0
+    }
+
+    /// Returns the level currently seen on a pin
+    fn read_level(&self, pin_num: usize) -> bool {
+        let levels = if self.output_enable & (1 << pin_num) != 0 {
+            self.output_levels
+        } else {
+            self.input_levels
+        };
+        levels & (1 << pin_num) != 0
+    }
+
+    /// Selects the function of a GPIO pin.
+    pub fn set_function(&mut self, pin_num: usize, func: u8) {
+        self.functions[pin_num] = func;
+        self.publish();
+    }
+
+    /// Writes the pin table to the state file, one line per pin
+    fn publish(&self) {
+        let Some(path) = &self.state_path else {
+            return;
+        };
+
+        let mut table = String::new();
+        for pin in 0..NUM_PINS {
+            let mask = 1 << pin;
+            let direction = if self.output_enable & mask != 0 {
+                "output"
+            } else {
+                "input"
+            };
+            let level = if self.read_level(pin) { "high" } else { "low" };
+            table.push_str(&format!(
+                "GP{pin:<2} {direction:<6} {level:<4} func={}\n",
+                self.functions[pin]
+            ));
+        }
+
+        // The simulator keeps running even if the state file cannot be written
+        let _ = fs::write(path, table);
+    }
+}
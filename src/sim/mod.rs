@@ -0,0 +1,89 @@
+//! Host-native simulator of the Pico shell.
+//!
+//! Built with the `sim` feature, the shell runs on the build machine: the PAC-backed
+//! UART, GPIO, timer and clock drivers are replaced by the simulated backends in this
+//! directory, and the terminal is driven through [`console::SimConsole`].
+//!
+//! Host tools connect through a stable symlink to the simulator's pty, and the
+//! simulated pin table is published to a state file. Both locations can be overridden:
+//! - `PICO_SIM_TTY` - symlink to the pty (defaults to `pico-sim-tty` in the temporary directory)
+//! - `PICO_SIM_GPIO_STATE` - pin table file (defaults to `pico-sim-gpio` in the temporary directory)
+
+pub mod console;
+
+use crate::cli::Cli;
+use crate::clocks::ClockAPI;
+use crate::constants::ONBOARD_LED_NUM;
+use crate::peripherals::gpio::Gpio;
+use crate::peripherals::timer::Timer;
+use crate::sim::console::SimConsole;
+use std::path::PathBuf;
+use std::{env, fs, os};
+
+/// Environment variable overriding the pty symlink location
+const TTY_LINK_ENV: &str = "PICO_SIM_TTY";
+
+/// Default pty symlink name inside the temporary directory
+const TTY_LINK_FILE: &str = "pico-sim-tty";
+
+/// Environment variable overriding the GPIO state file location
+const GPIO_STATE_ENV: &str = "PICO_SIM_GPIO_STATE";
+
+/// Default GPIO state file name inside the temporary directory
+const GPIO_STATE_FILE: &str = "pico-sim-gpio";
+
+/// Returns the path configured by `variable`, or `file_name` in the temporary directory
+fn configured_path(variable: &str, file_name: &str) -> PathBuf {
+    env::var_os(variable)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| env::temp_dir().join(file_name))
+}
+
+/// Runs the simulated firmware until the user quits with Ctrl-]
+///
+/// Mirrors the start-up sequence of the firmware entry point.
+pub fn run() -> ! {
+    let clocks = ClockAPI::new();
+    Timer::new().install();
+
+    let gpio_state_path = configured_path(GPIO_STATE_ENV, GPIO_STATE_FILE);
+    let mut pins = Gpio::new(Some(gpio_state_path.clone()));
+
+    // Set the onboard LED to output mode
+    pins.set_output(ONBOARD_LED_NUM);
+    pins.set_function(0, 0b010);
+    pins.set_function(1, 0b010);
+
+    let console = SimConsole::open();
+    let tty_link = configured_path(TTY_LINK_ENV, TTY_LINK_FILE);
+    if let Some(pty_path) = console.pty_path() {
+        let _ = fs::remove_file(&tty_link);
+        let _ = os::unix::fs::symlink(pty_path, &tty_link);
+    }
+
+    // stdin is already in raw mode, hence the explicit carriage returns
+    eprint!(
+        "Pico shell simulator (clk_peri {} Hz)\r\n",
+        clocks.uart_clock_freq()
+    );
+    match console.pty_path() {
+        Some(path) => eprint!("Serial port: {} -> {path}\r\n", tty_link.display()),
+        None => eprint!("Serial port: unavailable\r\n"),
+    }
+    eprint!("GPIO state:  {}\r\n", gpio_state_path.display());
+    eprint!("Press Ctrl-] to quit\r\n\r\n");
+
+    let mut cli = Cli::with_io(console);
+
+    pins.set_high(ONBOARD_LED_NUM);
+
+    // Make the clock frequencies available to the dashboard
+    clocks.publish();
+
+    // Hand the pins over to the CLI command handlers
+    pins.install();
+
+    loop {
+        cli.process_input();
+    }
+}
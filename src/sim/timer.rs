@@ -0,0 +1,35 @@
+//! Simulated uptime timer module for the host simulator.
+//!
+//! Mirrors the API of the RP2040 timer module, measuring the uptime with the host clock.
+
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Time the simulated timer was installed
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// Simulated microsecond counter
+pub struct Timer;
+
+impl Timer {
+    /// Creates a simulated timer
+    pub fn new() -> Self {
+        Timer
+    }
+
+    /// Starts the uptime count reported by [`uptime_us`]
+    pub fn install(self) {
+        let _ = START.set(Instant::now());
+    }
+}
+
+/// Returns the time since the timer was installed
+///
+/// # Returns
+///
+/// The uptime in microseconds, or `None` if no `Timer` has been installed yet
+pub fn uptime_us() -> Option<u64> {
+    START
+        .get()
+        .map(|start| start.elapsed().as_micros().try_into().unwrap_or(u64::MAX))
+}